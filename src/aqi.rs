@@ -1,56 +1,69 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::Arc;
-
 use serde::Deserialize;
 
-use crate::{CliError, SwayBarBlock};
+use crate::{block::MsbBlock, CliError, SwayBarBlock};
 
+const URI: &str = "https://api.waqi.info/feed/";
 // 成都三瓦窑
-const URI: &str = "https://api.waqi.info/feed/@1362?token=";
+const DEFAULT_STATION: &str = "@1362";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct AqiConfig {
+    /// aqicn.org station ID, e.g. `@1362`
+    pub(crate) station: String,
+    /// AQI to show in yellow
+    pub(crate) warning: u32,
+    /// AQI to show in red
+    pub(crate) critical: u32,
+}
+
+impl Default for AqiConfig {
+    fn default() -> Self {
+        Self {
+            station: DEFAULT_STATION.to_string(),
+            warning: 150,
+            critical: 200,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
-pub(crate) struct AqiFetcher {
-    aqi: Arc<AtomicU32>,
+pub(crate) struct AqiBlock {
+    config: AqiConfig,
 }
 
-impl AqiFetcher {
-    pub(crate) async fn new() -> Result<Self, CliError> {
-        let aqi = Arc::new(AtomicU32::new(0));
-        let aqi_clone = aqi.clone();
-        tokio::spawn(async move {
-            loop {
-                aqi_clone.store(0, Ordering::Relaxed);
-                if let Ok(i) = get_aqi().await {
-                    aqi_clone.store(i, Ordering::Relaxed);
-                }
-                tokio::time::sleep(std::time::Duration::from_secs(1800)).await;
-            }
-        });
-        Ok(Self { aqi })
+impl AqiBlock {
+    pub(crate) fn new(config: AqiConfig) -> Self {
+        Self { config }
     }
+}
+
+impl MsbBlock for AqiBlock {
+    const NAME: &'static str = "aqi";
+    const DEFAULT_INTERVAL: Option<u64> = Some(1800);
 
-    pub(crate) fn get(&self) -> Option<SwayBarBlock> {
-        let aqi: u32 = self.aqi.load(Ordering::Relaxed);
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        let aqi = get_aqi(&self.config.station).await?;
 
         if aqi > 0 {
-            let color = if aqi >= 200 {
+            let color = if aqi >= self.config.critical {
                 Some(crate::COLOR_RED.to_string())
-            } else if aqi >= 150 {
+            } else if aqi >= self.config.warning {
                 Some(crate::COLOR_YELLOW.to_string())
             } else {
                 None
             };
-            Some(SwayBarBlock {
+            Ok(vec![SwayBarBlock {
                 name: "aqi".into(),
                 color,
                 full_text: format!("AQI: {aqi}"),
-                min_width: Some(9),
+                min_width: Some(9.into()),
                 ..Default::default()
-            })
+            }])
         } else {
-            None
+            Ok(Vec::new())
         }
     }
 }
@@ -65,14 +78,14 @@ struct AqiCnReplyData {
     aqi: u32,
 }
 
-async fn get_aqi() -> Result<u32, CliError> {
+async fn get_aqi(station: &str) -> Result<u32, CliError> {
     let aqicn_key = if let Ok(k) = std::env::var("AQI_CN_KEY") {
         k
     } else {
         return Ok(0);
     };
 
-    let body = reqwest::get(&format!("{URI}{aqicn_key}"))
+    let body = reqwest::get(&format!("{URI}{station}?token={aqicn_key}"))
         .await?
         .text()
         .await?;
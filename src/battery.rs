@@ -1,64 +1,482 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::{HashMap, VecDeque};
+
+use serde::Deserialize;
+
 use crate::{
-    fs::{read_file, read_file_as_i64, read_file_as_u64},
+    block::MsbBlock,
+    fs::{read_dir, read_file, read_file_as_i64, read_file_as_u64},
+    notify::{send_notification, Urgency},
     CliError, SwayBarBlock,
 };
 
-const SYSFS_BASE_DIR: &str = "/sys/class/power_supply/BAT0";
+pub(crate) const SYSFS_POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
 
 const EMOJI_CHARGING: &str = "⚡︎";
+const EMOJI_PLUGGED: &str = "🔌";
 const EMOJI_BATTERY_GOOD: &str = "🔋";
 const EMOJI_BATTERY_EMPTY: &str = "🪫";
 
-pub(crate) fn get_battery() -> Result<Option<SwayBarBlock>, CliError> {
-    if !std::fs::exists(SYSFS_BASE_DIR).unwrap_or_default() {
-        return Ok(None);
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct BatteryConfig {
+    /// Percentage at or below which to show in yellow
+    pub(crate) warning: u64,
+    /// Percentage at or below which to show in red
+    pub(crate) critical: u64,
+    /// Show each battery in its own block instead of combined
+    pub(crate) per_battery: bool,
+    /// What is treated as 100%
+    pub(crate) full_reference: BatteryFullReference,
+    /// Number of power samples used to estimate remaining time
+    pub(crate) history_size: usize,
+    /// Send desktop notification when discharging to these percentages
+    pub(crate) notify_levels: Vec<u64>,
+    /// Percentage at or below which to run `critical_command`
+    pub(crate) critical_level: u64,
+    /// Command and its arguments to run at `critical_level`, e.g.
+    /// `["systemctl", "suspend"]`
+    pub(crate) critical_command: Vec<String>,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            warning: 40,
+            critical: 20,
+            per_battery: false,
+            full_reference: BatteryFullReference::FullDesign,
+            history_size: 30,
+            notify_levels: vec![15, 5],
+            critical_level: 3,
+            critical_command: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BatteryFullReference {
+    /// Last full capacity, `energy_full` or `charge_full`
+    Full,
+    /// Design capacity, `energy_full_design` or `charge_full_design`
+    FullDesign,
+}
+
+impl BatteryFullReference {
+    fn suffix(&self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::FullDesign => "full_design",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BatteryBlock {
+    config: BatteryConfig,
+    /// Power history indexed by battery name, empty name for combined
+    histories: HashMap<String, PowerHistory>,
+    /// Notification levels fired in current discharge cycle
+    notified_levels: Vec<u64>,
+    /// Whether critical command fired in current discharge cycle
+    critical_fired: bool,
+    /// Whether to send notifications and run critical command, disabled
+    /// for one-off commands like `msb once`
+    alerts: bool,
+}
+
+impl BatteryBlock {
+    pub(crate) fn new(config: BatteryConfig, alerts: bool) -> Self {
+        Self {
+            config,
+            histories: HashMap::new(),
+            notified_levels: Vec::new(),
+            critical_fired: false,
+            alerts,
+        }
+    }
+
+    // Each action only fires once per discharge cycle
+    async fn check_alerts(&mut self, battery: &BatteryInfo) {
+        if battery.status != BatteryStatus::Discharging {
+            self.notified_levels.clear();
+            self.critical_fired = false;
+            return;
+        }
+        let percent = battery.capacity;
+
+        // Only notify the lowest level crossed
+        if let Some(level) = self
+            .config
+            .notify_levels
+            .iter()
+            .filter(|l| percent <= **l && !self.notified_levels.contains(l))
+            .min()
+            .copied()
+        {
+            self.notified_levels.extend(
+                self.config.notify_levels.iter().filter(|l| percent <= **l),
+            );
+            // The lowest level is critical
+            let urgency = if Some(level)
+                == self.config.notify_levels.iter().min().copied()
+            {
+                Urgency::Critical
+            } else {
+                Urgency::Normal
+            };
+            if let Err(e) = send_notification(
+                "Battery low",
+                &format!("Battery level is {percent}%"),
+                "battery-caution",
+                urgency,
+            )
+            .await
+            {
+                log::warn!("Failed to send battery notification: {e}");
+            }
+        }
+
+        if !self.critical_fired
+            && percent <= self.config.critical_level
+            && !self.config.critical_command.is_empty()
+        {
+            self.critical_fired = true;
+            let cmd = self.config.critical_command.join(" ");
+            log::warn!("Battery level is {percent}%, running `{cmd}`");
+            match tokio::process::Command::new(&self.config.critical_command[0])
+                .args(&self.config.critical_command[1..])
+                .status()
+                .await
+            {
+                Ok(status) if !status.success() => {
+                    log::error!("Command `{cmd}` failed: {status}");
+                }
+                Err(e) => {
+                    log::error!("Failed to run command `{cmd}`: {e}");
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn render(&mut self, battery: &BatteryInfo) -> SwayBarBlock {
+        let history = self.histories.entry(battery.name.clone()).or_default();
+        if let Some(energy) = battery.energy {
+            history.push(
+                battery.status,
+                energy.power,
+                self.config.history_size,
+            );
+        }
+        let mut block = battery.to_block(&self.config, history.average());
+        if !self.notified_levels.is_empty() {
+            block.urgent = Some(true);
+        }
+        block
+    }
+}
+
+impl MsbBlock for BatteryBlock {
+    const NAME: &'static str = "battery";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        let batteries = get_batteries(self.config.full_reference)?;
+        if batteries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let combined = BatteryInfo::combine(&batteries);
+        if self.alerts {
+            self.check_alerts(&combined).await;
+        }
+        if self.config.per_battery {
+            Ok(batteries.iter().map(|bat| self.render(bat)).collect())
+        } else {
+            Ok(vec![self.render(&combined)])
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct BatteryInfo {
+    /// Sysfs name like `BAT0`. Empty for combined batteries
+    name: String,
+    /// None when driver only provides `capacity`
+    energy: Option<BatteryEnergy>,
+    /// Percentage from `capacity` when `energy` is None
+    capacity: u64,
+    status: BatteryStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum BatteryStatus {
+    Charging,
+    Discharging,
+    NotCharging,
+    Full,
+    #[default]
+    Unknown,
+}
+
+impl From<&str> for BatteryStatus {
+    fn from(s: &str) -> Self {
+        match s {
+            "Charging" => Self::Charging,
+            "Discharging" => Self::Discharging,
+            "Not charging" => Self::NotCharging,
+            "Full" => Self::Full,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Rolling history of power samples of the same battery status
+#[derive(Debug, Clone, Default)]
+struct PowerHistory {
+    status: BatteryStatus,
+    /// Power in uW, oldest first
+    samples: VecDeque<u64>,
+}
+
+impl PowerHistory {
+    fn push(&mut self, status: BatteryStatus, power: u64, size: usize) {
+        // Power of charging is irrelevant to power of discharging
+        if status != self.status {
+            self.samples.clear();
+            self.status = status;
+        }
+        self.samples.push_back(power);
+        while self.samples.len() > size.max(1) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Exponentially weighted moving average in uW, newer samples weigh more
+    fn average(&self) -> f64 {
+        let alpha = 2.0 / (self.samples.len() as f64 + 1.0);
+        let mut samples = self.samples.iter().map(|s| *s as f64);
+        let first = samples.next().unwrap_or_default();
+        samples.fold(first, |avg, s| alpha * s + (1.0 - alpha) * avg)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct BatteryEnergy {
+    /// Energy in uWh
+    now: u64,
+    /// Energy in uWh
+    full: u64,
+    /// Current power consumption in uW
+    power: u64,
+}
+
+impl BatteryEnergy {
+    // Some drivers expose `energy_*` in uWh and `power_now` in uW, others
+    // expose `charge_*` in uAh and `current_now` in uA which require
+    // `voltage_now` in uV to convert.
+    fn retrieve(
+        dir: &str,
+        full_reference: BatteryFullReference,
+    ) -> Option<Self> {
+        let suffix = full_reference.suffix();
+        let energy = if let (Ok(now), Ok(full)) = (
+            read_file_as_u64(&format!("{dir}/energy_now")),
+            read_file_as_u64(&format!("{dir}/energy_{suffix}")),
+        ) {
+            let power = read_file_as_i64(&format!("{dir}/power_now"))
+                .unwrap_or_default()
+                .unsigned_abs();
+            Self { now, full, power }
+        } else {
+            let now = read_file_as_u64(&format!("{dir}/charge_now")).ok()?;
+            let full =
+                read_file_as_u64(&format!("{dir}/charge_{suffix}")).ok()?;
+            let voltage =
+                read_file_as_u64(&format!("{dir}/voltage_now")).ok()?;
+            let current = read_file_as_i64(&format!("{dir}/current_now"))
+                .unwrap_or_default()
+                .unsigned_abs();
+            Self {
+                now: uah_to_uwh(now, voltage),
+                full: uah_to_uwh(full, voltage),
+                power: uah_to_uwh(current, voltage),
+            }
+        };
+        if energy.full > 0 {
+            Some(energy)
+        } else {
+            None
+        }
+    }
+
+    fn percent(&self) -> u64 {
+        (self.now as f64 / self.full as f64 * 100.0) as u64
+    }
+}
+
+// uAh * uV / 1000000 = uWh, also works for uA to uW
+fn uah_to_uwh(uah: u64, uv: u64) -> u64 {
+    (uah as u128 * uv as u128 / 1_000_000) as u64
+}
+
+impl BatteryInfo {
+    fn retrieve(
+        name: &str,
+        full_reference: BatteryFullReference,
+    ) -> Result<Self, CliError> {
+        let dir = format!("{SYSFS_POWER_SUPPLY_DIR}/{name}");
+        let energy = BatteryEnergy::retrieve(&dir, full_reference);
+        let capacity = match energy {
+            Some(e) => e.percent(),
+            None => {
+                read_file_as_u64(&format!("{dir}/capacity")).map_err(|e| {
+                    CliError::from(format!(
+                        "Battery {name} has no usable energy_*, charge_* \
+                         or capacity: {e}"
+                    ))
+                })?
+            }
+        };
+        Ok(Self {
+            name: name.to_string(),
+            energy,
+            capacity,
+            status: read_file(&format!("{dir}/status"))?.as_str().into(),
+        })
     }
 
-    let now = read_file_as_u64(&format!("{SYSFS_BASE_DIR}/energy_now"))?;
-    let full =
-        read_file_as_u64(&format!("{SYSFS_BASE_DIR}/energy_full_design"))?;
-    let is_charging =
-        read_file(&format!("{SYSFS_BASE_DIR}/status"))? != "Discharging";
+    // Discharging if any battery is discharging, then charging if any is
+    // charging. When any battery lacks energy information, use average
+    // capacity.
+    fn combine(batteries: &[Self]) -> Self {
+        let energies: Option<Vec<BatteryEnergy>> =
+            batteries.iter().map(|b| b.energy).collect();
+        let energy = energies.map(|energies| BatteryEnergy {
+            now: energies.iter().map(|e| e.now).sum(),
+            full: energies.iter().map(|e| e.full).sum(),
+            power: energies.iter().map(|e| e.power).sum(),
+        });
+        let capacity = match energy {
+            Some(e) => e.percent(),
+            None => {
+                batteries.iter().map(|b| b.capacity).sum::<u64>()
+                    / batteries.len().max(1) as u64
+            }
+        };
+        let statuses: Vec<BatteryStatus> =
+            batteries.iter().map(|b| b.status).collect();
+        let status = if statuses.contains(&BatteryStatus::Discharging) {
+            BatteryStatus::Discharging
+        } else if statuses.contains(&BatteryStatus::Charging) {
+            BatteryStatus::Charging
+        } else if statuses.iter().all(|s| *s == BatteryStatus::Full) {
+            BatteryStatus::Full
+        } else if statuses.contains(&BatteryStatus::NotCharging) {
+            BatteryStatus::NotCharging
+        } else {
+            BatteryStatus::Unknown
+        };
+        Self {
+            name: String::new(),
+            energy,
+            capacity,
+            status,
+        }
+    }
 
-    let percent = (now as f64 / full as f64 * 100.0) as u64;
+    /// Remaining hours till empty or full using specified power in uW.
+    /// None if not charging or discharging or power is unknown.
+    fn time_left(&self, power: f64) -> Option<f64> {
+        let energy = self.energy?;
+        if power < 1.0 {
+            return None;
+        }
+        match self.status {
+            BatteryStatus::Charging if energy.now < energy.full => {
+                Some((energy.full - energy.now) as f64 / power)
+            }
+            BatteryStatus::Discharging => Some(energy.now as f64 / power),
+            _ => None,
+        }
+    }
 
-    // Current power consumption in uW
-    let consumption = read_file_as_i64(&format!("{SYSFS_BASE_DIR}/power_now"))?;
+    fn to_block(&self, config: &BatteryConfig, power: f64) -> SwayBarBlock {
+        let percent = self.capacity;
+        let time_left = self.time_left(power);
 
-    let time_left = if is_charging {
-        if now >= full {
-            0.0
+        let (charge_str, color) = if self.status == BatteryStatus::Charging {
+            (EMOJI_CHARGING, None)
+        } else if self.status != BatteryStatus::Discharging {
+            (EMOJI_PLUGGED, None)
+        } else if time_left.map(|t| t < 0.5).unwrap_or_default() {
+            (EMOJI_BATTERY_EMPTY, Some(crate::COLOR_RED.to_string()))
+        } else if percent > config.warning {
+            (EMOJI_BATTERY_GOOD, None)
+        } else if percent > config.critical {
+            (EMOJI_BATTERY_GOOD, Some(crate::COLOR_YELLOW.to_string()))
         } else {
-            (full - now) as f64 / consumption as f64
-        }
-    } else {
-        now as f64 / consumption as f64
-    };
-    let time_left_hour = time_left as u8;
-    let time_left_min = (time_left.fract() * 60.0) as u8;
-
-    let (charge_str, color) = if is_charging {
-        (EMOJI_CHARGING, None)
-    } else if time_left < 0.5 {
-        (EMOJI_BATTERY_EMPTY, Some(crate::COLOR_RED.to_string()))
-    } else if percent > 40 {
-        (EMOJI_BATTERY_GOOD, None)
-    } else if percent > 20 {
-        (EMOJI_BATTERY_GOOD, Some(crate::COLOR_YELLOW.to_string()))
-    } else {
-        (EMOJI_BATTERY_EMPTY, Some(crate::COLOR_RED.to_string()))
-    };
-
-    Ok(Some(SwayBarBlock {
-        name: "battery".into(),
-        color,
-        full_text: format!(
-            "{charge_str}: {percent}% \
-            {time_left_hour:02}:{time_left_min:02}"
-        ),
-        min_width: Some(12),
-        ..Default::default()
-    }))
+            (EMOJI_BATTERY_EMPTY, Some(crate::COLOR_RED.to_string()))
+        };
+
+        let prefix = if self.name.is_empty() {
+            charge_str.to_string()
+        } else {
+            format!("{charge_str} {}", self.name)
+        };
+
+        let time_left_str = match time_left {
+            // Longer than 100 hours is nonsense
+            Some(time_left) if time_left < 100.0 => {
+                let time_left_hour = time_left as u8;
+                let time_left_min = (time_left.fract() * 60.0) as u8;
+                format!("{time_left_hour:02}:{time_left_min:02}")
+            }
+            _ => "--:--".to_string(),
+        };
+
+        SwayBarBlock {
+            name: "battery".into(),
+            instance: (!self.name.is_empty()).then(|| self.name.clone()),
+            color,
+            full_text: format!("{prefix}: {percent}% {time_left_str}"),
+            short_text: Some(format!("{prefix}: {percent}%")),
+            min_width: Some(12.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Names of batteries powering the system, excluding the ones of
+/// peripheral devices like wireless mouse.
+pub(crate) fn get_battery_names() -> Result<Vec<String>, CliError> {
+    let mut ret = Vec::new();
+    if !std::fs::exists(SYSFS_POWER_SUPPLY_DIR).unwrap_or_default() {
+        return Ok(ret);
+    }
+    let mut names = read_dir(SYSFS_POWER_SUPPLY_DIR)?;
+    names.sort_unstable();
+    for name in names {
+        let dir = format!("{SYSFS_POWER_SUPPLY_DIR}/{name}");
+        if read_file(&format!("{dir}/type")).ok().as_deref() != Some("Battery")
+        {
+            continue;
+        }
+        if read_file(&format!("{dir}/scope")).ok().as_deref() == Some("Device")
+        {
+            continue;
+        }
+        ret.push(name);
+    }
+    Ok(ret)
+}
+
+fn get_batteries(
+    full_reference: BatteryFullReference,
+) -> Result<Vec<BatteryInfo>, CliError> {
+    get_battery_names()?
+        .iter()
+        .map(|name| BatteryInfo::retrieve(name, full_reference))
+        .collect()
 }
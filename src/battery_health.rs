@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::Deserialize;
+
+use crate::{
+    battery::{get_battery_names, SYSFS_POWER_SUPPLY_DIR},
+    block::MsbBlock,
+    fs::{read_file_as_u64, write_file},
+    swaybar::BUTTON_LEFT,
+    CliError, SwayBarBlock, SwayBarClickEvent,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct BatteryHealthConfig {
+    /// Wear level percentage to show in yellow
+    pub(crate) warning: u64,
+    /// Wear level percentage to show in red
+    pub(crate) critical: u64,
+    /// Charge end thresholds in ascending order to cycle through on left
+    /// click
+    pub(crate) end_thresholds: Vec<u64>,
+}
+
+impl Default for BatteryHealthConfig {
+    fn default() -> Self {
+        Self {
+            warning: 20,
+            critical: 40,
+            end_thresholds: vec![60, 80, 100],
+        }
+    }
+}
+
+impl BatteryHealthConfig {
+    pub(crate) fn validate(&self) -> Result<(), CliError> {
+        if let Some(t) =
+            self.end_thresholds.iter().find(|t| !(1..=100).contains(*t))
+        {
+            return Err(format!(
+                "battery_health end threshold {t} should be within 1 to 100"
+            )
+            .into());
+        }
+        if !self.end_thresholds.is_sorted_by(|a, b| a < b) {
+            return Err(format!(
+                "battery_health end_thresholds {:?} should be in strictly \
+                 ascending order",
+                self.end_thresholds
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BatteryHealthBlock {
+    config: BatteryHealthConfig,
+}
+
+impl BatteryHealthBlock {
+    pub(crate) fn new(config: BatteryHealthConfig) -> Self {
+        Self { config }
+    }
+
+    // Set end threshold to the next one in config. Kernel refuses end
+    // threshold not bigger than start threshold, skip those.
+    fn cycle_end_threshold(&self, name: &str) -> Result<(), CliError> {
+        let dir = format!("{SYSFS_POWER_SUPPLY_DIR}/{name}");
+        let file_path = format!("{dir}/charge_control_end_threshold");
+        let cur = read_file_as_u64(&file_path).map_err(|e| {
+            CliError::from(format!(
+                "Battery {name} does not support charge threshold: {e}"
+            ))
+        })?;
+        let start =
+            read_file_as_u64(&format!("{dir}/charge_control_start_threshold"))
+                .unwrap_or_default();
+        let mut thresholds =
+            self.config.end_thresholds.iter().filter(|t| **t > start);
+        let next = thresholds
+            .clone()
+            .find(|t| **t > cur)
+            .or_else(|| thresholds.next());
+        if let Some(next) = next {
+            log::info!("Setting {name} charge end threshold to {next}%");
+            write_file(&file_path, &next.to_string())?;
+        } else {
+            log::info!(
+                "No end threshold bigger than {name} charge start threshold \
+                 {start}%"
+            );
+        }
+        Ok(())
+    }
+}
+
+impl MsbBlock for BatteryHealthBlock {
+    const NAME: &'static str = "battery_health";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        let mut ret = Vec::new();
+        for name in get_battery_names()? {
+            ret.push(BatteryHealth::retrieve(&name).to_block(&self.config));
+        }
+        Ok(ret)
+    }
+
+    async fn on_click(
+        &mut self,
+        event: &SwayBarClickEvent,
+    ) -> Result<(), CliError> {
+        if event.button != BUTTON_LEFT {
+            return Ok(());
+        }
+        let name = match event.instance.as_ref() {
+            Some(n) => n.to_string(),
+            None => match get_battery_names()?.into_iter().next() {
+                Some(n) => n,
+                None => return Ok(()),
+            },
+        };
+        self.cycle_end_threshold(&name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct BatteryHealth {
+    name: String,
+    /// Percentage of capacity lost comparing to design capacity
+    wear_level: Option<u64>,
+    cycle_count: Option<u64>,
+    start_threshold: Option<u64>,
+    end_threshold: Option<u64>,
+}
+
+impl BatteryHealth {
+    // Every property is optional as they are driver specific
+    fn retrieve(name: &str) -> Self {
+        let dir = format!("{SYSFS_POWER_SUPPLY_DIR}/{name}");
+        let read = |prop: &str| read_file_as_u64(&format!("{dir}/{prop}")).ok();
+
+        let full_and_design =
+            match (read("energy_full"), read("energy_full_design")) {
+                (Some(full), Some(design)) => Some((full, design)),
+                _ => read("charge_full").zip(read("charge_full_design")),
+            };
+        let wear_level = full_and_design
+            .filter(|(_, design)| *design > 0)
+            .map(|(full, design)| 100u64.saturating_sub(full * 100 / design));
+
+        Self {
+            name: name.to_string(),
+            wear_level,
+            // Some drivers report 0 when not supported
+            cycle_count: read("cycle_count").filter(|c| *c > 0),
+            start_threshold: read("charge_control_start_threshold"),
+            end_threshold: read("charge_control_end_threshold"),
+        }
+    }
+
+    fn to_block(&self, config: &BatteryHealthConfig) -> SwayBarBlock {
+        let mut items: Vec<String> = Vec::new();
+        if let Some(wear_level) = self.wear_level {
+            items.push(format!("wear {wear_level}%"));
+        }
+        if let Some(cycle_count) = self.cycle_count {
+            items.push(format!("{cycle_count} cycles"));
+        }
+        match (self.start_threshold, self.end_threshold) {
+            (Some(start), Some(end)) => items.push(format!("{start}-{end}%")),
+            (None, Some(end)) => items.push(format!("-{end}%")),
+            _ => (),
+        }
+        let color = match self.wear_level {
+            Some(w) if w >= config.critical => {
+                Some(crate::COLOR_RED.to_string())
+            }
+            Some(w) if w >= config.warning => {
+                Some(crate::COLOR_YELLOW.to_string())
+            }
+            _ => None,
+        };
+
+        SwayBarBlock {
+            name: "battery_health".into(),
+            instance: Some(self.name.clone()),
+            full_text: format!("♥ {}: {}", self.name, items.join(", ")),
+            short_text: self.wear_level.map(|w| format!("♥ {}%", 100 - w)),
+            color,
+            ..Default::default()
+        }
+    }
+}
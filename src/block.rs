@@ -0,0 +1,294 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use tokio::sync::{mpsc, watch};
+
+use crate::{
+    aqi::AqiBlock,
+    battery::BatteryBlock,
+    battery_health::BatteryHealthBlock,
+    config::{BlockConfig, BlockEntry},
+    cpu::CpuBlock,
+    cpufreq::CpuFreqBlock,
+    disk::DiskBlock,
+    diskio::DiskIoBlock,
+    ethernet::EthernetBlock,
+    ip::IpBlock,
+    memory::MemoryBlock,
+    psi::PsiBlock,
+    rate::RateBlock,
+    sound::SoundBlock,
+    temp::TempBlock,
+    time::TimeBlock,
+    wifi::WifiBlock,
+    CliError, SwayBarBlock, SwayBarClickEvent,
+};
+
+/// Every block is running in its own tokio task refreshing at its own
+/// interval and sending the result to the emitter.
+pub(crate) trait MsbBlock: Send + 'static {
+    /// Name used by the placeholder block when failed to refresh
+    const NAME: &'static str;
+
+    /// Refresh interval in seconds used when not defined in config. `None`
+    /// means the global interval.
+    const DEFAULT_INTERVAL: Option<u64> = None;
+
+    /// Return empty Vec if nothing to show.
+    fn refresh(
+        &mut self,
+    ) -> impl Future<Output = Result<Vec<SwayBarBlock>, CliError>> + Send;
+
+    /// Invoked on click event targeting blocks generated by this producer.
+    /// Refresh will be triggered after this.
+    fn on_click(
+        &mut self,
+        _event: &SwayBarClickEvent,
+    ) -> impl Future<Output = Result<(), CliError>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Resolved when something changed and refresh should happen before
+    /// next tick. Never resolved by default.
+    fn wait_event(&mut self) -> impl Future<Output = ()> + Send {
+        std::future::pending()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BlockUpdate {
+    /// Index of the producer in config
+    pub(crate) index: usize,
+    pub(crate) blocks: Vec<SwayBarBlock>,
+    /// Error message when failed to refresh
+    pub(crate) error: Option<String>,
+}
+
+/// Channels connecting block producer to the emitter
+#[derive(Debug)]
+pub(crate) struct BlockContext {
+    pub(crate) index: usize,
+    pub(crate) update_sender: mpsc::Sender<BlockUpdate>,
+    pub(crate) click_receiver: mpsc::Receiver<SwayBarClickEvent>,
+    /// Stop polling when true
+    pub(crate) paused: watch::Receiver<bool>,
+}
+
+/// Spawn producers for all blocks. Return the receiver of their updates and
+/// click event senders in the same order of `entries`.
+/// When `oneshot` is true, only the first refresh is wanted, side effects
+/// like battery notifications are skipped.
+pub(crate) fn spawn_blocks(
+    entries: &[BlockEntry],
+    global_interval: u64,
+    paused: watch::Receiver<bool>,
+    oneshot: bool,
+) -> (
+    mpsc::Receiver<BlockUpdate>,
+    Vec<mpsc::Sender<SwayBarClickEvent>>,
+) {
+    let (update_sender, update_receiver) = mpsc::channel(16);
+    let mut click_senders = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let (click_sender, click_receiver) = mpsc::channel(4);
+        click_senders.push(click_sender);
+        spawn_block(
+            entry,
+            global_interval,
+            oneshot,
+            BlockContext {
+                index,
+                update_sender: update_sender.clone(),
+                click_receiver,
+                paused: paused.clone(),
+            },
+        );
+    }
+    (update_receiver, click_senders)
+}
+
+/// Wait for the first refresh of every block, return in the same order of
+/// `entries`
+pub(crate) async fn refresh_once(
+    entries: &[BlockEntry],
+    global_interval: u64,
+) -> Vec<BlockUpdate> {
+    let (_pause_sender, pause_receiver) = watch::channel(false);
+    let (mut update_receiver, _click_senders) =
+        spawn_blocks(entries, global_interval, pause_receiver, true);
+    let mut results: Vec<Option<BlockUpdate>> = vec![None; entries.len()];
+    while results.iter().any(Option::is_none) {
+        match update_receiver.recv().await {
+            Some(update) => {
+                if let Some(result) = results.get_mut(update.index) {
+                    result.get_or_insert(update);
+                }
+            }
+            None => break,
+        }
+    }
+    results.into_iter().flatten().collect()
+}
+
+fn spawn_block(
+    entry: &BlockEntry,
+    global_interval: u64,
+    oneshot: bool,
+    context: BlockContext,
+) {
+    let interval = entry.interval;
+    macro_rules! spawn {
+        ($block:expr) => {
+            spawn_producer($block, interval, global_interval, context)
+        };
+    }
+    match &entry.block {
+        BlockConfig::Aqi(c) => spawn!(AqiBlock::new(c.clone())),
+        BlockConfig::Rate(c) => spawn!(RateBlock::new(c.clone())),
+        BlockConfig::Wifi(c) => spawn!(WifiBlock::new(c.clone())),
+        BlockConfig::Ethernet(c) => spawn!(EthernetBlock::new(c.clone())),
+        BlockConfig::Ip(c) => spawn!(IpBlock::new(c.clone())),
+        BlockConfig::Cpu(c) => spawn!(CpuBlock::new(c.clone())),
+        BlockConfig::CpuFreq(c) => spawn!(CpuFreqBlock::new(c.clone())),
+        BlockConfig::Memory(c) => spawn!(MemoryBlock::new(c.clone())),
+        BlockConfig::Psi(c) => spawn!(PsiBlock::new(c.clone())),
+        BlockConfig::Temp(c) => spawn!(TempBlock::new(c.clone())),
+        BlockConfig::Disk(c) => spawn!(DiskBlock::new(c.clone())),
+        BlockConfig::DiskIo(c) => spawn!(DiskIoBlock::new(c.clone())),
+        BlockConfig::Sound(c) => spawn!(SoundBlock::new(c.clone())),
+        BlockConfig::Battery(c) => {
+            spawn!(BatteryBlock::new(c.clone(), !oneshot))
+        }
+        BlockConfig::BatteryHealth(c) => {
+            spawn!(BatteryHealthBlock::new(c.clone()))
+        }
+        BlockConfig::Time(c) => spawn!(TimeBlock::new(c.clone())),
+    }
+}
+
+fn spawn_producer<B: MsbBlock>(
+    mut block: B,
+    interval: Option<u64>,
+    global_interval: u64,
+    mut context: BlockContext,
+) {
+    let period = Duration::from_secs(
+        interval.or(B::DEFAULT_INTERVAL).unwrap_or(global_interval),
+    );
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval
+            .set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut error: Option<BlockError> = None;
+        let mut last_refresh = Instant::now();
+        loop {
+            let mut click_result = Ok(());
+            let mut pause_changed = false;
+            let mut rerender_error = false;
+            tokio::select! {
+                _ = interval.tick() => (),
+                Some(event) = context.click_receiver.recv() => {
+                    if let Some(e) = error.as_mut() {
+                        // Clicking on error block toggles error message.
+                        // Refreshing would drop error of click handler
+                        // which is kept till next tick.
+                        e.show_detail = !e.show_detail;
+                        rerender_error = e.from_click;
+                    } else {
+                        click_result = block.on_click(&event).await;
+                    }
+                }
+                Ok(()) = context.paused.changed() => pause_changed = true,
+                _ = block.wait_event() => (),
+            }
+            let was_paused = *context.paused.borrow_and_update();
+            if was_paused && context.paused.wait_for(|p| !p).await.is_err() {
+                break;
+            }
+            // swaybar in `hide` mode pauses and resumes on every modifier
+            // key press, only refresh when a tick came due meanwhile.
+            if pause_changed && last_refresh.elapsed() < period {
+                continue;
+            }
+            if was_paused {
+                interval.reset();
+            }
+            let blocks = if rerender_error {
+                error.iter().map(|e| e.to_block(B::NAME)).collect()
+            } else {
+                last_refresh = Instant::now();
+                let result = match click_result {
+                    Ok(()) => block.refresh().await.map_err(|e| (e, false)),
+                    Err(e) => Err((e, true)),
+                };
+                match result {
+                    Ok(blocks) => {
+                        error = None;
+                        blocks
+                    }
+                    Err((e, from_click)) => {
+                        if from_click {
+                            log::error!(
+                                "Failed to handle click on {} block: {e}",
+                                B::NAME
+                            );
+                        } else {
+                            log::error!(
+                                "Failed to refresh {} block: {e}",
+                                B::NAME
+                            );
+                        }
+                        let show_detail = error
+                            .as_ref()
+                            .map(|e| e.show_detail)
+                            .unwrap_or_default();
+                        let e = BlockError {
+                            error_msg: e.to_string(),
+                            show_detail,
+                            from_click,
+                        };
+                        let blocks = vec![e.to_block(B::NAME)];
+                        error = Some(e);
+                        blocks
+                    }
+                }
+            };
+            let update = BlockUpdate {
+                index: context.index,
+                blocks,
+                error: error.as_ref().map(|e| e.error_msg.clone()),
+            };
+            if context.update_sender.send(update).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct BlockError {
+    error_msg: String,
+    show_detail: bool,
+    /// Failed in `on_click()` instead of `refresh()`
+    from_click: bool,
+}
+
+impl BlockError {
+    fn to_block(&self, name: &str) -> SwayBarBlock {
+        let full_text = if self.show_detail {
+            format!("{name}: {}", self.error_msg)
+        } else {
+            format!("{name}: ERR")
+        };
+        SwayBarBlock {
+            name: name.to_string(),
+            full_text,
+            short_text: Some(format!("{name}: ERR")),
+            color: Some(crate::COLOR_RED.to_string()),
+            urgent: Some(true),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use clap::{Parser, Subcommand};
+
+use crate::{config::MsbConfig, logger::parse_level, CliError};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "My Sway Bar")]
+pub(crate) struct MsbCli {
+    /// Path of config file, default to `$XDG_CONFIG_HOME/msb/config.toml`
+    #[arg(short, long, global = true)]
+    pub(crate) config: Option<String>,
+    /// Default seconds between two refreshes of a block
+    #[arg(short, long, global = true)]
+    pub(crate) interval: Option<u64>,
+    /// Log level: off, error, warn, info, debug or trace
+    #[arg(short, long, global = true)]
+    pub(crate) log_level: Option<String>,
+    #[command(subcommand)]
+    pub(crate) command: Option<MsbCommand>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Subcommand)]
+pub(crate) enum MsbCommand {
+    /// Run as swaybar status command (default)
+    #[default]
+    Run,
+    /// Print one status line and exit
+    Once,
+    /// Show available blocks and what they detect on this machine
+    ListBlocks,
+    /// Validate config file
+    CheckConfig,
+}
+
+impl MsbCli {
+    /// Path of config file to load, None means default configuration
+    pub(crate) fn config_path(&self) -> Result<Option<String>, CliError> {
+        match self.config.as_deref() {
+            Some(path) if !std::path::Path::new(path).exists() => {
+                Err(format!("Config file {path} does not exist").into())
+            }
+            Some(path) => Ok(Some(path.to_string())),
+            None => Ok(MsbConfig::find_config_path()),
+        }
+    }
+
+    /// Load config file with command line overrides applied
+    pub(crate) fn load_config(&self) -> Result<MsbConfig, CliError> {
+        let mut config = match self.config_path()? {
+            Some(path) => MsbConfig::load_from(&path)?,
+            None => MsbConfig::default(),
+        };
+        if let Some(interval) = self.interval {
+            if interval == 0 {
+                return Err("--interval should be bigger than 0".into());
+            }
+            config.interval = interval;
+        }
+        if let Some(level) = self.log_level.as_deref() {
+            parse_level(level)?;
+        }
+        Ok(config)
+    }
+}
@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::Deserialize;
+
+use crate::{
+    aqi::AqiConfig, battery::BatteryConfig,
+    battery_health::BatteryHealthConfig, cpu::CpuConfig,
+    cpufreq::CpuFreqConfig, disk::DiskConfig, diskio::DiskIoConfig,
+    ethernet::EthernetConfig, ip::IpConfig, logger::LogConfig,
+    memory::MemoryConfig, psi::PsiConfig, rate::RateConfig, sound::SoundConfig,
+    temp::TempConfig, time::TimeConfig, wifi::WifiConfig, CliError,
+};
+
+const CONFIG_FILE_NAME: &str = "msb/config.toml";
+const DEFAULT_INTERVAL: u64 = 10;
+
+// Example:
+//
+//  interval = 10
+//
+//  [log]
+//  level = "info"
+//  target = "file"
+//
+//  [[block]]
+//  type = "rate"
+//  iface = "wlp3s0"
+//
+//  [[block]]
+//  type = "cpu"
+//  interval = 2
+//  warning = 50
+//  critical = 80
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct MsbConfig {
+    /// Default seconds between two refreshes of a block
+    pub(crate) interval: u64,
+    /// Blocks shown from left to right
+    #[serde(rename = "block")]
+    pub(crate) blocks: Vec<BlockEntry>,
+    pub(crate) log: LogConfig,
+}
+
+impl Default for MsbConfig {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_INTERVAL,
+            blocks: vec![
+                BlockConfig::Aqi(AqiConfig::default()).into(),
+                BlockConfig::Rate(RateConfig::default()).into(),
+                BlockConfig::Wifi(WifiConfig::default()).into(),
+                BlockConfig::Cpu(CpuConfig::default()).into(),
+                BlockConfig::Temp(TempConfig::default()).into(),
+                BlockConfig::Sound(SoundConfig::default()).into(),
+                BlockConfig::Battery(BatteryConfig::default()).into(),
+                BlockConfig::Time(TimeConfig::default()).into(),
+            ],
+            log: LogConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub(crate) struct BlockEntry {
+    /// Seconds between two refreshes of this block, overriding the block
+    /// specific default and the global interval
+    #[serde(default)]
+    pub(crate) interval: Option<u64>,
+    #[serde(flatten)]
+    pub(crate) block: BlockConfig,
+}
+
+impl From<BlockConfig> for BlockEntry {
+    fn from(block: BlockConfig) -> Self {
+        Self {
+            interval: None,
+            block,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum BlockConfig {
+    Aqi(AqiConfig),
+    Rate(RateConfig),
+    Wifi(WifiConfig),
+    Ethernet(EthernetConfig),
+    Ip(IpConfig),
+    Cpu(CpuConfig),
+    #[serde(rename = "cpufreq")]
+    CpuFreq(CpuFreqConfig),
+    Memory(MemoryConfig),
+    Psi(PsiConfig),
+    Temp(TempConfig),
+    Disk(DiskConfig),
+    #[serde(rename = "diskio")]
+    DiskIo(DiskIoConfig),
+    Sound(SoundConfig),
+    Battery(BatteryConfig),
+    BatteryHealth(BatteryHealthConfig),
+    Time(TimeConfig),
+}
+
+impl BlockConfig {
+    /// Every block type with default settings
+    pub(crate) fn all() -> Vec<Self> {
+        vec![
+            Self::Aqi(AqiConfig::default()),
+            Self::Rate(RateConfig::default()),
+            Self::Wifi(WifiConfig::default()),
+            Self::Ethernet(EthernetConfig::default()),
+            Self::Ip(IpConfig::default()),
+            Self::Cpu(CpuConfig::default()),
+            Self::CpuFreq(CpuFreqConfig::default()),
+            Self::Memory(MemoryConfig::default()),
+            Self::Psi(PsiConfig::default()),
+            Self::Temp(TempConfig::default()),
+            Self::Disk(DiskConfig::default()),
+            Self::DiskIo(DiskIoConfig::default()),
+            Self::Sound(SoundConfig::default()),
+            Self::Battery(BatteryConfig::default()),
+            Self::BatteryHealth(BatteryHealthConfig::default()),
+            Self::Time(TimeConfig::default()),
+        ]
+    }
+
+    /// The `type` used in config file
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Self::Aqi(_) => "aqi",
+            Self::Rate(_) => "rate",
+            Self::Wifi(_) => "wifi",
+            Self::Ethernet(_) => "ethernet",
+            Self::Ip(_) => "ip",
+            Self::Cpu(_) => "cpu",
+            Self::CpuFreq(_) => "cpufreq",
+            Self::Memory(_) => "memory",
+            Self::Psi(_) => "psi",
+            Self::Temp(_) => "temp",
+            Self::Disk(_) => "disk",
+            Self::DiskIo(_) => "diskio",
+            Self::Sound(_) => "sound",
+            Self::Battery(_) => "battery",
+            Self::BatteryHealth(_) => "battery_health",
+            Self::Time(_) => "time",
+        }
+    }
+}
+
+impl MsbConfig {
+    /// Existing one of `$XDG_CONFIG_HOME/msb/config.toml` and
+    /// `$HOME/.config/msb/config.toml`. None means default configuration
+    /// should be used.
+    pub(crate) fn find_config_path() -> Option<String> {
+        default_config_path().filter(|path| std::path::Path::new(path).exists())
+    }
+
+    pub(crate) fn load_from(path: &str) -> Result<Self, CliError> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            CliError::from(format!("Failed to read config file {path}: {e}"))
+        })?;
+        let config: Self = toml::from_str(&content).map_err(|e| {
+            CliError::from(format!("Invalid config file {path}: {e}"))
+        })?;
+        config.validate().map_err(|e| {
+            CliError::from(format!("Invalid config file {path}: {e}"))
+        })?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), CliError> {
+        if self.interval == 0 {
+            return Err("interval should be bigger than 0".into());
+        }
+        if self.blocks.is_empty() {
+            return Err("no block defined".into());
+        }
+        self.log.validate()?;
+        for entry in &self.blocks {
+            if entry.interval == Some(0) {
+                return Err("block interval should be bigger than 0".into());
+            }
+            match &entry.block {
+                BlockConfig::Time(c) => c.validate()?,
+                BlockConfig::Wifi(c) => c.validate()?,
+                BlockConfig::BatteryHealth(c) => c.validate()?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_config_path() -> Option<String> {
+    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        if !dir.is_empty() {
+            return Some(format!("{dir}/{CONFIG_FILE_NAME}"));
+        }
+    }
+    std::env::var("HOME")
+        .ok()
+        .map(|home| format!("{home}/.config/{CONFIG_FILE_NAME}"))
+}
@@ -1,12 +1,45 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
-use crate::{fs::read_file, CliError, SwayBarBlock};
+use serde::Deserialize;
 
-const INTERVAL: u64 = 500; // 0.5 second
+use crate::{block::MsbBlock, fs::read_file, CliError, SwayBarBlock};
 
-#[derive(Debug)]
+const SPARKLINE_CHARS: [char; 5] = ['▁', '▂', '▃', '▅', '▇'];
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct CpuConfig {
+    /// Usage percentage to show in yellow
+    pub(crate) warning: u64,
+    /// Usage percentage to show in red
+    pub(crate) critical: u64,
+    /// Show the busiest core and its usage
+    pub(crate) busiest_core: bool,
+    /// Show usage of each core as sparkline
+    pub(crate) sparkline: bool,
+    /// Show percentage of time waiting for I/O
+    pub(crate) iowait: bool,
+    /// Show percentage of time stolen by hypervisor
+    pub(crate) steal: bool,
+}
+
+impl Default for CpuConfig {
+    fn default() -> Self {
+        Self {
+            warning: 50,
+            critical: 80,
+            busiest_core: false,
+            sparkline: false,
+            iowait: false,
+            steal: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 struct CpuUsageStat {
     user: u64,
     nice: u64,
@@ -46,8 +79,27 @@ impl From<&str> for CpuUsageStat {
 
 impl CpuUsageStat {
     fn cpu_usage_percent(&self, old: &Self) -> u64 {
-        (self.work_time() - old.work_time()) * 100
-            / (self.total_time() - old.total_time())
+        self.percent_since(old, self.work_time(), old.work_time())
+    }
+
+    fn iowait_percent(&self, old: &Self) -> u64 {
+        self.percent_since(old, self.iowait, old.iowait)
+    }
+
+    fn steal_percent(&self, old: &Self) -> u64 {
+        self.percent_since(old, self.steal, old.steal)
+    }
+
+    // Counters might go backwards when CPU is hot plugged, treat it as no
+    // time spent instead of overflowing.
+    fn percent_since(&self, old: &Self, new_value: u64, old_value: u64) -> u64 {
+        let total = self.total_time().saturating_sub(old.total_time());
+        new_value
+            .saturating_sub(old_value)
+            .saturating_mul(100)
+            .checked_div(total)
+            .unwrap_or_default()
+            .min(100)
     }
 
     fn work_time(&self) -> u64 {
@@ -64,36 +116,135 @@ impl CpuUsageStat {
             .saturating_add(self.idle)
             .saturating_add(self.steal)
     }
+}
 
+#[derive(Debug, Clone, Default)]
+struct CpuStat {
+    total: CpuUsageStat,
+    /// Indexed by CPU ID. Offline CPUs are not included.
+    cores: BTreeMap<u32, CpuUsageStat>,
+}
+
+impl CpuStat {
     fn retrieve() -> Result<Self, CliError> {
         let stat = read_file("/proc/stat")?;
+        let mut total = None;
+        let mut cores = BTreeMap::new();
 
-        if let Some(line) = stat.split('\n').next() {
-            Ok(CpuUsageStat::from(line))
+        for line in stat.lines() {
+            let Some(name) = line.split_ascii_whitespace().next() else {
+                continue;
+            };
+            if name == "cpu" {
+                total = Some(CpuUsageStat::from(line));
+            } else if let Some(Ok(id)) =
+                name.strip_prefix("cpu").map(u32::from_str)
+            {
+                cores.insert(id, CpuUsageStat::from(line));
+            }
+        }
+        if let Some(total) = total {
+            Ok(Self { total, cores })
         } else {
-            Err("Failed to read first line of /proc/state".into())
+            Err("Failed to find aggregate cpu line in /proc/stat".into())
+        }
+    }
+
+    /// Usage percentage of each core. Core missing in old sample is
+    /// compared against boot time.
+    fn core_usage_percents(&self, old: &Self) -> Vec<(u32, u64)> {
+        let zero = CpuUsageStat::default();
+        self.cores
+            .iter()
+            .map(|(id, stat)| {
+                (
+                    *id,
+                    stat.cpu_usage_percent(old.cores.get(id).unwrap_or(&zero)),
+                )
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CpuBlock {
+    config: CpuConfig,
+    /// Sample of previous refresh
+    last_stat: Option<CpuStat>,
+}
+
+impl CpuBlock {
+    pub(crate) fn new(config: CpuConfig) -> Self {
+        Self {
+            config,
+            last_stat: None,
         }
     }
 }
 
-pub(crate) async fn get_cpu() -> Result<SwayBarBlock, CliError> {
-    let old_stat = CpuUsageStat::retrieve()?;
-    tokio::time::sleep(std::time::Duration::from_millis(INTERVAL)).await;
-    let new_stat = CpuUsageStat::retrieve()?;
-    let percent = new_stat.cpu_usage_percent(&old_stat);
-    let color = if percent >= 80 {
+impl MsbBlock for CpuBlock {
+    const NAME: &'static str = "cpu";
+    const DEFAULT_INTERVAL: Option<u64> = Some(2);
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        let new_stat = CpuStat::retrieve()?;
+        // The first refresh shows the average usage since boot
+        let old_stat = self.last_stat.take().unwrap_or_default();
+        let block = get_cpu(&self.config, &old_stat, &new_stat);
+        self.last_stat = Some(new_stat);
+        Ok(vec![block])
+    }
+}
+
+fn get_cpu(
+    config: &CpuConfig,
+    old_stat: &CpuStat,
+    new_stat: &CpuStat,
+) -> SwayBarBlock {
+    let percent = new_stat.total.cpu_usage_percent(&old_stat.total);
+    let core_percents = new_stat.core_usage_percents(old_stat);
+    let color = if percent >= config.critical {
         Some(crate::COLOR_RED.to_string())
-    } else if percent >= 50 {
+    } else if percent >= config.warning {
         Some(crate::COLOR_YELLOW.to_string())
     } else {
         None
     };
 
-    Ok(SwayBarBlock {
+    let short_text = format!("C: {percent:>3}%");
+    let mut full_text = short_text.clone();
+    if config.busiest_core {
+        if let Some((id, core_percent)) =
+            core_percents.iter().max_by_key(|(_, p)| *p)
+        {
+            full_text.push_str(&format!(" #{id} {core_percent:>3}%"));
+        }
+    }
+    if config.sparkline && !core_percents.is_empty() {
+        full_text.push(' ');
+        full_text.extend(core_percents.iter().map(|(_, p)| sparkline_char(*p)));
+    }
+    if config.iowait {
+        let iowait = new_stat.total.iowait_percent(&old_stat.total);
+        full_text.push_str(&format!(" io {iowait}%"));
+    }
+    if config.steal {
+        let steal = new_stat.total.steal_percent(&old_stat.total);
+        full_text.push_str(&format!(" st {steal}%"));
+    }
+
+    SwayBarBlock {
         name: "cpu".into(),
-        full_text: format!("C: {percent:>3}%"),
-        min_width: Some(7),
+        short_text: (full_text != short_text).then_some(short_text),
+        full_text,
+        min_width: Some(7.into()),
         color,
         ..Default::default()
-    })
+    }
+}
+
+fn sparkline_char(percent: u64) -> char {
+    let index = (percent as usize * SPARKLINE_CHARS.len() / 100)
+        .min(SPARKLINE_CHARS.len() - 1);
+    SPARKLINE_CHARS[index]
 }
@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::Deserialize;
+
+use crate::{
+    block::MsbBlock,
+    fs::{read_file, read_file_as_u64},
+    CliError, SwayBarBlock,
+};
+
+const SYSFS_CPU_DIR: &str = "/sys/devices/system/cpu";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct CpuFreqConfig {
+    /// 1 minute load per online CPU in percentage to show in yellow
+    pub(crate) warning: u64,
+    /// 1 minute load per online CPU in percentage to show in red
+    pub(crate) critical: u64,
+    /// Whether to show scaling governor and energy performance preference
+    pub(crate) governor: bool,
+}
+
+impl Default for CpuFreqConfig {
+    fn default() -> Self {
+        Self {
+            warning: 70,
+            critical: 100,
+            governor: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CpuFreqBlock {
+    config: CpuFreqConfig,
+}
+
+impl CpuFreqBlock {
+    pub(crate) fn new(config: CpuFreqConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl MsbBlock for CpuFreqBlock {
+    const NAME: &'static str = "cpufreq";
+    const DEFAULT_INTERVAL: Option<u64> = Some(2);
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        Ok(vec![get_cpufreq(&self.config)?])
+    }
+}
+
+fn get_cpufreq(config: &CpuFreqConfig) -> Result<SwayBarBlock, CliError> {
+    let cpus = get_online_cpus()?;
+    let load = LoadAvg::retrieve()?;
+    let mut items: Vec<String> = Vec::new();
+
+    // Virtual machines might have no cpufreq
+    let freqs: Vec<u64> = cpus
+        .iter()
+        .filter_map(|id| {
+            read_file_as_u64(&format!(
+                "{SYSFS_CPU_DIR}/cpu{id}/cpufreq/scaling_cur_freq"
+            ))
+            .ok()
+        })
+        .collect();
+    if let Some(max) = freqs.iter().max() {
+        let avg = freqs.iter().sum::<u64>() / freqs.len() as u64;
+        items.push(format!("{}/{} GHz", khz_to_ghz(avg), khz_to_ghz(*max)));
+    }
+
+    items.push(format!(
+        "L: {:.2} {:.2} {:.2}",
+        load.one, load.five, load.fifteen
+    ));
+
+    if config.governor {
+        if let Some(id) = cpus.first() {
+            let dir = format!("{SYSFS_CPU_DIR}/cpu{id}/cpufreq");
+            let policy: Vec<String> =
+                ["scaling_governor", "energy_performance_preference"]
+                    .iter()
+                    .filter_map(|f| read_file(&format!("{dir}/{f}")).ok())
+                    .collect();
+            if !policy.is_empty() {
+                items.push(policy.join("/"));
+            }
+        }
+    }
+
+    let load_percent = (load.one * 100.0 / cpus.len().max(1) as f64) as u64;
+    let color = if load_percent >= config.critical {
+        Some(crate::COLOR_RED.to_string())
+    } else if load_percent >= config.warning {
+        Some(crate::COLOR_YELLOW.to_string())
+    } else {
+        None
+    };
+
+    Ok(SwayBarBlock {
+        name: "cpufreq".into(),
+        full_text: items.join(" "),
+        short_text: Some(format!("L: {:.2}", load.one)),
+        color,
+        ..Default::default()
+    })
+}
+
+// Show 1 decimal place like `2.4`
+fn khz_to_ghz(khz: u64) -> String {
+    format!("{}.{}", khz / 1000000, khz % 1000000 / 100000)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LoadAvg {
+    one: f64,
+    five: f64,
+    fifteen: f64,
+}
+
+impl LoadAvg {
+    fn retrieve() -> Result<Self, CliError> {
+        let content = read_file("/proc/loadavg")?;
+        let mut loads = content.split_ascii_whitespace();
+        let mut next = || -> Result<f64, CliError> {
+            Ok(loads
+                .next()
+                .ok_or_else(|| {
+                    CliError::from(format!(
+                        "Invalid /proc/loadavg content: {content}"
+                    ))
+                })?
+                .parse::<f64>()?)
+        };
+        Ok(Self {
+            one: next()?,
+            five: next()?,
+            fifteen: next()?,
+        })
+    }
+}
+
+/// IDs of online CPUs
+fn get_online_cpus() -> Result<Vec<u32>, CliError> {
+    parse_cpu_list(&read_file(&format!("{SYSFS_CPU_DIR}/online"))?)
+}
+
+/// Parse CPU list like `0-3,5,7-8`
+fn parse_cpu_list(content: &str) -> Result<Vec<u32>, CliError> {
+    let mut ret = Vec::new();
+    for range in content.split(',').filter(|s| !s.is_empty()) {
+        if let Some((start, end)) = range.split_once('-') {
+            ret.extend(start.parse::<u32>()?..=end.parse::<u32>()?);
+        } else {
+            ret.push(range.parse::<u32>()?);
+        }
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0").unwrap(), vec![0]);
+        assert_eq!(parse_cpu_list("0-3").unwrap(), vec![0, 1, 2, 3]);
+        assert_eq!(
+            parse_cpu_list("0-3,5,7-8").unwrap(),
+            vec![0, 1, 2, 3, 5, 7, 8]
+        );
+        assert_eq!(parse_cpu_list("1,").unwrap(), vec![1]);
+        assert!(parse_cpu_list("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_cpu_list_invalid() {
+        assert!(parse_cpu_list("a").is_err());
+        assert!(parse_cpu_list("0-").is_err());
+        assert!(parse_cpu_list("0-3,x").is_err());
+    }
+}
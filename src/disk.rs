@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+use std::os::unix::fs::MetadataExt;
+
+use serde::Deserialize;
+
+use crate::{
+    block::MsbBlock, fs::read_file, rate::bytes_to_human, CliError,
+    SwayBarBlock,
+};
+
+const MOUNT_POINTS: [&str; 2] = ["/", "/home"];
+const FS_TYPES: [&str; 6] =
+    ["ext4", "xfs", "btrfs", "f2fs", "bcachefs", "vfat"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct DiskConfig {
+    /// Mount points to show, the ones not exist are ignored
+    pub(crate) mounts: Vec<String>,
+    /// Whether to include mount points found in `/proc/self/mounts`
+    pub(crate) auto: bool,
+    /// Filesystem types to include when `auto` is enabled
+    pub(crate) fs_types: Vec<String>,
+    /// Used space percentage to show in yellow
+    pub(crate) warning: u64,
+    /// Used space percentage to show in red
+    pub(crate) critical: u64,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        Self {
+            mounts: MOUNT_POINTS.iter().map(|s| s.to_string()).collect(),
+            auto: false,
+            fs_types: FS_TYPES.iter().map(|s| s.to_string()).collect(),
+            warning: 80,
+            critical: 90,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DiskBlock {
+    config: DiskConfig,
+}
+
+impl DiskBlock {
+    pub(crate) fn new(config: DiskConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl MsbBlock for DiskBlock {
+    const NAME: &'static str = "disk";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        get_disks(&self.config)
+    }
+}
+
+fn get_disks(config: &DiskConfig) -> Result<Vec<SwayBarBlock>, CliError> {
+    let mut mounts = config.mounts.clone();
+    if config.auto {
+        for mount in get_mounts(&config.fs_types)? {
+            if !mounts.contains(&mount) {
+                mounts.push(mount);
+            }
+        }
+    }
+
+    let mut ret = Vec::new();
+    // Mount points sharing the same filesystem are shown only once, e.g.
+    // `/home` is not a dedicated partition.
+    let mut devs = HashSet::new();
+    for mount in mounts {
+        let Ok(metadata) = std::fs::metadata(&mount) else {
+            log::debug!("Mount point {mount} does not exist, ignoring");
+            continue;
+        };
+        if devs.insert(metadata.dev()) {
+            ret.push(DiskUsage::retrieve(&mount)?.to_block(config));
+        }
+    }
+    Ok(ret)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DiskUsage {
+    mount: String,
+    /// Bytes available to unprivileged user
+    available: u64,
+    /// Used percentage excluding space reserved for root, same as df(1)
+    used_percent: u64,
+}
+
+impl DiskUsage {
+    fn retrieve(mount: &str) -> Result<Self, CliError> {
+        let stat = nix::sys::statvfs::statvfs(mount).map_err(|e| {
+            CliError::from(format!("Failed to statvfs {mount}: {e}"))
+        })?;
+        let fragment_size = stat.fragment_size() as u64;
+        let used =
+            (stat.blocks() as u64).saturating_sub(stat.blocks_free() as u64);
+        let available = stat.blocks_available() as u64;
+        Ok(Self {
+            mount: mount.to_string(),
+            available: available.saturating_mul(fragment_size),
+            used_percent: used
+                .saturating_mul(100)
+                .checked_div(used + available)
+                .unwrap_or_default(),
+        })
+    }
+
+    fn to_block(&self, config: &DiskConfig) -> SwayBarBlock {
+        let color = if self.used_percent >= config.critical {
+            Some(crate::COLOR_RED.to_string())
+        } else if self.used_percent >= config.warning {
+            Some(crate::COLOR_YELLOW.to_string())
+        } else {
+            None
+        };
+        SwayBarBlock {
+            name: "disk".into(),
+            instance: Some(self.mount.clone()),
+            full_text: format!(
+                "{}: {} free, {}% used",
+                self.mount,
+                bytes_to_human(self.available),
+                self.used_percent
+            ),
+            short_text: Some(format!("{}: {}%", self.mount, self.used_percent)),
+            color,
+            ..Default::default()
+        }
+    }
+}
+
+/// Mount points of specified filesystem types
+fn get_mounts(fs_types: &[String]) -> Result<Vec<String>, CliError> {
+    let content = read_file("/proc/self/mounts")?;
+    let mut ret = Vec::new();
+    // Line format:
+    //  /dev/nvme0n1p2 / btrfs rw,relatime,subvol=/root 0 0
+    for line in content.lines() {
+        let mut items = line.split_ascii_whitespace();
+        if let (Some(_), Some(mount), Some(fs_type)) =
+            (items.next(), items.next(), items.next())
+        {
+            if fs_types.iter().any(|t| t == fs_type) {
+                let mount = unescape_mount(mount);
+                if !ret.contains(&mount) {
+                    ret.push(mount);
+                }
+            }
+        }
+    }
+    Ok(ret)
+}
+
+// Kernel escapes space, tab, newline and backslash in mount point as octal
+// like `\040`.
+fn unescape_mount(mount: &str) -> String {
+    let mut ret = String::new();
+    let mut rest = mount;
+    while let Some(pos) = rest.find('\\') {
+        ret.push_str(&rest[..pos]);
+        let octal = rest
+            .get(pos + 1..pos + 4)
+            .filter(|o| o.bytes().all(|b| (b'0'..=b'7').contains(&b)))
+            .unwrap_or_default();
+        if let Ok(c) = u8::from_str_radix(octal, 8) {
+            ret.push(c as char);
+            rest = &rest[pos + 4..];
+        } else {
+            ret.push('\\');
+            rest = &rest[pos + 1..];
+        }
+    }
+    ret.push_str(rest);
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unescape_mount() {
+        assert_eq!(unescape_mount("/"), "/");
+        assert_eq!(unescape_mount("/mnt/my\\040disk"), "/mnt/my disk");
+        assert_eq!(unescape_mount("/a\\011b\\012c"), "/a\tb\nc");
+        assert_eq!(unescape_mount("/back\\134slash"), "/back\\slash");
+        assert_eq!(unescape_mount("\\040\\040"), "  ");
+    }
+
+    #[test]
+    fn test_unescape_mount_invalid_escape() {
+        assert_eq!(unescape_mount("/a\\"), "/a\\");
+        assert_eq!(unescape_mount("/a\\04"), "/a\\04");
+        assert_eq!(unescape_mount("/a\\089"), "/a\\089");
+        assert_eq!(unescape_mount("/a\\+12"), "/a\\+12");
+        assert_eq!(unescape_mount("/a\\é00"), "/a\\é00");
+    }
+}
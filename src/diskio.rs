@@ -0,0 +1,156 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use serde::Deserialize;
+
+use crate::{
+    block::MsbBlock,
+    fs::{read_dir, read_file},
+    memory::SYSFS_BLOCK_DIR,
+    rate::{bytes_per_second, bytes_to_human},
+    CliError, SwayBarBlock,
+};
+
+// The `stat` file always counts in 512 bytes sectors regardless of the
+// logical block size of device.
+const SECTOR_SIZE: u64 = 512;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct DiskIoConfig {
+    /// Block devices like `nvme0n1` to show, default to all non-virtual
+    /// devices
+    pub(crate) devices: Vec<String>,
+    /// Show each device separately instead of combined
+    pub(crate) per_device: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DiskBytesSample {
+    time: Instant,
+    read: u64,
+    written: u64,
+}
+
+impl DiskBytesSample {
+    fn retrieve(dev: &str) -> Result<Self, CliError> {
+        let file_path = format!("{SYSFS_BLOCK_DIR}/{dev}/stat");
+        let content = read_file(&file_path).map_err(|e| {
+            CliError::from(format!(
+                "Failed to read {file_path}, is {dev} a block device? {e}"
+            ))
+        })?;
+        // Fields are documented in kernel Documentation/block/stat.rst,
+        // the 3rd is read sectors and the 7th is write sectors.
+        let values: Vec<&str> = content.split_ascii_whitespace().collect();
+        match (values.get(2), values.get(6)) {
+            (Some(read), Some(written)) => Ok(Self {
+                time: Instant::now(),
+                read: read.parse::<u64>()?.saturating_mul(SECTOR_SIZE),
+                written: written.parse::<u64>()?.saturating_mul(SECTOR_SIZE),
+            }),
+            _ => {
+                Err(format!("Invalid content of {file_path}: {content}").into())
+            }
+        }
+    }
+
+    /// Bytes per second of read and write since old sample
+    fn speed_since(&self, old: &Self) -> (u64, u64) {
+        let elapsed = self.time.duration_since(old.time);
+        (
+            bytes_per_second(old.read, self.read, elapsed),
+            bytes_per_second(old.written, self.written, elapsed),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DiskIoBlock {
+    config: DiskIoConfig,
+    /// Samples of previous refresh indexed by device name
+    last_samples: HashMap<String, DiskBytesSample>,
+}
+
+impl DiskIoBlock {
+    pub(crate) fn new(config: DiskIoConfig) -> Self {
+        Self {
+            config,
+            last_samples: HashMap::new(),
+        }
+    }
+}
+
+impl MsbBlock for DiskIoBlock {
+    const NAME: &'static str = "diskio";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        let devs = if self.config.devices.is_empty() {
+            get_physical_devices()?
+        } else {
+            self.config.devices.clone()
+        };
+
+        let mut speeds: Vec<(String, u64, u64)> = Vec::new();
+        let mut samples = HashMap::new();
+        for dev in devs {
+            let sample = DiskBytesSample::retrieve(&dev)?;
+            // The first refresh has nothing to compare with, show 0 B/s
+            let (read, write) = self
+                .last_samples
+                .get(&dev)
+                .map(|old| sample.speed_since(old))
+                .unwrap_or_default();
+            samples.insert(dev.clone(), sample);
+            speeds.push((dev, read, write));
+        }
+        // Drop samples of removed devices
+        self.last_samples = samples;
+
+        if self.config.per_device {
+            Ok(speeds
+                .iter()
+                .map(|(dev, read, write)| get_diskio(Some(dev), *read, *write))
+                .collect())
+        } else {
+            let read = speeds.iter().map(|(_, r, _)| r).sum();
+            let write = speeds.iter().map(|(_, _, w)| w).sum();
+            Ok(vec![get_diskio(None, read, write)])
+        }
+    }
+}
+
+fn get_diskio(dev: Option<&str>, read: u64, write: u64) -> SwayBarBlock {
+    let read = bytes_to_human(read);
+    let write = bytes_to_human(write);
+    SwayBarBlock {
+        name: "diskio".into(),
+        instance: dev.map(|d| d.to_string()),
+        full_text: format!(
+            "{:>8}: r {: >9}/s w {: >9}/s",
+            dev.unwrap_or("disk"),
+            read,
+            write
+        ),
+        short_text: Some(format!("r {read}/s w {write}/s")),
+        min_width: Some(28.into()),
+        ..Default::default()
+    }
+}
+
+/// Block devices backed by hardware, excluding virtual ones like loop,
+/// zram and device mapper.
+fn get_physical_devices() -> Result<Vec<String>, CliError> {
+    let mut ret = Vec::new();
+    for dev in read_dir(SYSFS_BLOCK_DIR)? {
+        if std::fs::exists(format!("{SYSFS_BLOCK_DIR}/{dev}/device"))
+            .unwrap_or_default()
+        {
+            ret.push(dev);
+        }
+    }
+    ret.sort_unstable();
+    Ok(ret)
+}
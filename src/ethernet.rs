@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::Deserialize;
+
+use crate::{
+    block::MsbBlock,
+    fs::{read_dir, read_file, read_file_as_i64},
+    net::NetEventListener,
+    CliError, SwayBarBlock,
+};
+
+const SYSFS_NET_DIR: &str = "/sys/class/net";
+// ARPHRD_ETHER of linux/if_arp.h
+const ARPHRD_ETHER: &str = "1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct EthernetConfig {
+    /// Interfaces to show, default to all physical ethernet interfaces
+    pub(crate) ifaces: Vec<String>,
+    /// Negotiated speed in Mb/s below which to show in yellow
+    pub(crate) expected_speed: u64,
+}
+
+impl Default for EthernetConfig {
+    fn default() -> Self {
+        Self {
+            ifaces: Vec::new(),
+            expected_speed: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct EthernetBlock {
+    config: EthernetConfig,
+    net_events: NetEventListener,
+}
+
+impl EthernetBlock {
+    pub(crate) fn new(config: EthernetConfig) -> Self {
+        Self {
+            config,
+            net_events: NetEventListener::default(),
+        }
+    }
+}
+
+impl MsbBlock for EthernetBlock {
+    const NAME: &'static str = "ethernet";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        let ifaces = if self.config.ifaces.is_empty() {
+            get_ethernet_ifaces()?
+        } else {
+            self.config.ifaces.clone()
+        };
+        Ok(ifaces
+            .iter()
+            .map(|iface| EthernetLink::retrieve(iface).to_block(&self.config))
+            .collect())
+    }
+
+    // Cable plug and unplug
+    async fn wait_event(&mut self) {
+        self.net_events.wait().await
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct EthernetLink {
+    iface: String,
+    exists: bool,
+    carrier: bool,
+    /// Negotiated speed in Mb/s
+    speed: Option<u64>,
+    duplex: Option<String>,
+    operstate: String,
+}
+
+impl EthernetLink {
+    // Reading `carrier`, `speed` and `duplex` fails with EINVAL when
+    // interface is administratively down, treat them as unknown.
+    fn retrieve(iface: &str) -> Self {
+        let dir = format!("{SYSFS_NET_DIR}/{iface}");
+        if !std::fs::exists(&dir).unwrap_or_default() {
+            return Self {
+                iface: iface.to_string(),
+                ..Default::default()
+            };
+        }
+        Self {
+            iface: iface.to_string(),
+            exists: true,
+            carrier: read_file_as_i64(&format!("{dir}/carrier"))
+                .map(|c| c == 1)
+                .unwrap_or_default(),
+            // Driver reports -1 when speed is unknown
+            speed: read_file_as_i64(&format!("{dir}/speed"))
+                .ok()
+                .and_then(|s| u64::try_from(s).ok()),
+            duplex: read_file(&format!("{dir}/duplex"))
+                .ok()
+                .filter(|d| d != "unknown"),
+            operstate: read_file(&format!("{dir}/operstate"))
+                .unwrap_or_else(|_| "unknown".to_string()),
+        }
+    }
+
+    fn to_block(&self, config: &EthernetConfig) -> SwayBarBlock {
+        let (text, color) = if !self.exists {
+            ("missing".to_string(), Some(crate::COLOR_RED.to_string()))
+        } else if !self.carrier {
+            (
+                format!("no carrier ({})", self.operstate),
+                Some(crate::COLOR_RED.to_string()),
+            )
+        } else {
+            let mut items: Vec<String> = Vec::new();
+            if let Some(speed) = self.speed {
+                items.push(speed_to_human(speed));
+            }
+            if let Some(duplex) = self.duplex.as_ref() {
+                items.push(duplex.to_string());
+            }
+            // Virtual NIC has no speed or duplex
+            if self.operstate != "up" || items.is_empty() {
+                items.push(self.operstate.to_string());
+            }
+            let color = match self.speed {
+                Some(speed) if speed < config.expected_speed => {
+                    Some(crate::COLOR_YELLOW.to_string())
+                }
+                _ => None,
+            };
+            (items.join(" "), color)
+        };
+
+        SwayBarBlock {
+            name: "ethernet".into(),
+            instance: Some(self.iface.clone()),
+            full_text: format!("🖧 {}: {text}", self.iface),
+            short_text: Some(format!("🖧 {text}")),
+            color,
+            ..Default::default()
+        }
+    }
+}
+
+fn speed_to_human(mbps: u64) -> String {
+    if mbps >= 1000 && mbps.is_multiple_of(100) {
+        if mbps.is_multiple_of(1000) {
+            format!("{}G", mbps / 1000)
+        } else {
+            format!("{}.{}G", mbps / 1000, mbps % 1000 / 100)
+        }
+    } else {
+        format!("{mbps}M")
+    }
+}
+
+/// Physical ethernet interfaces, excluding virtual and WiFi ones
+fn get_ethernet_ifaces() -> Result<Vec<String>, CliError> {
+    let mut ret = Vec::new();
+    for iface in read_dir(SYSFS_NET_DIR)? {
+        let dir = format!("{SYSFS_NET_DIR}/{iface}");
+        let is_ether = read_file(&format!("{dir}/type"))
+            .map(|t| t == ARPHRD_ETHER)
+            .unwrap_or_default();
+        let is_physical =
+            std::fs::exists(format!("{dir}/device")).unwrap_or_default();
+        let is_wifi =
+            std::fs::exists(format!("{dir}/phy80211")).unwrap_or_default();
+        if is_ether && is_physical && !is_wifi {
+            ret.push(iface);
+        }
+    }
+    ret.sort_unstable();
+    Ok(ret)
+}
@@ -21,6 +21,15 @@ pub(crate) fn read_file_as_i64(file_path: &str) -> Result<i64, CliError> {
     Ok(content.trim().parse::<i64>()?)
 }
 
+pub(crate) fn write_file(
+    file_path: &str,
+    content: &str,
+) -> Result<(), CliError> {
+    std::fs::write(file_path, content).map_err(|e| {
+        format!("Failed to write '{content}' to {file_path}: {e}").into()
+    })
+}
+
 pub(crate) fn read_dir(dir_path: &str) -> Result<Vec<String>, CliError> {
     let mut files = Vec::new();
     for entry in std::fs::read_dir(dir_path)? {
@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::net::Ipv6Addr;
+
+use serde::Deserialize;
+
+use crate::{
+    block::MsbBlock,
+    net::{resolve_iface, NetEventListener, IFACE_AUTO},
+    swaybar::{BUTTON_LEFT, BUTTON_RIGHT},
+    CliError, SwayBarBlock, SwayBarClickEvent,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct IpConfig {
+    /// Interface to show address of, `auto` means the one holding default
+    /// route
+    pub(crate) iface: String,
+    /// Whether to hide the address on start, e.g. for screen sharing
+    pub(crate) mask: bool,
+}
+
+impl Default for IpConfig {
+    fn default() -> Self {
+        Self {
+            iface: IFACE_AUTO.to_string(),
+            mask: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum IpFamily {
+    #[default]
+    Ipv4,
+    Ipv6Global,
+    Ipv6LinkLocal,
+}
+
+impl IpFamily {
+    fn next(self) -> Self {
+        match self {
+            Self::Ipv4 => Self::Ipv6Global,
+            Self::Ipv6Global => Self::Ipv6LinkLocal,
+            Self::Ipv6LinkLocal => Self::Ipv4,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Ipv4 => "IPv4",
+            Self::Ipv6Global => "IPv6",
+            Self::Ipv6LinkLocal => "link-local",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct IpBlock {
+    config: IpConfig,
+    family: IpFamily,
+    mask: bool,
+    net_events: NetEventListener,
+}
+
+impl IpBlock {
+    pub(crate) fn new(config: IpConfig) -> Self {
+        let mask = config.mask;
+        Self {
+            config,
+            family: IpFamily::default(),
+            mask,
+            net_events: NetEventListener::default(),
+        }
+    }
+}
+
+impl MsbBlock for IpBlock {
+    const NAME: &'static str = "ip";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        let Some(iface_name) = resolve_iface(&self.config.iface).await? else {
+            return Ok(vec![SwayBarBlock {
+                name: "ip".into(),
+                full_text: "🌐 no default route".to_string(),
+                color: Some(crate::COLOR_YELLOW.to_string()),
+                ..Default::default()
+            }]);
+        };
+        let address = get_address(&iface_name, self.family).await?;
+        let text = match address {
+            Some(_) if self.mask => "***".to_string(),
+            Some(address) => address,
+            None => format!("no {}", self.family.name()),
+        };
+
+        Ok(vec![SwayBarBlock {
+            name: "ip".into(),
+            instance: Some(iface_name.clone()),
+            full_text: format!("🌐 {iface_name}: {text}"),
+            short_text: Some(format!("🌐 {text}")),
+            ..Default::default()
+        }])
+    }
+
+    async fn on_click(
+        &mut self,
+        event: &SwayBarClickEvent,
+    ) -> Result<(), CliError> {
+        match event.button {
+            BUTTON_LEFT => self.family = self.family.next(),
+            BUTTON_RIGHT => self.mask = !self.mask,
+            _ => (),
+        }
+        Ok(())
+    }
+
+    async fn wait_event(&mut self) {
+        self.net_events.wait().await
+    }
+}
+
+/// First address of specified family in `address/prefix` format
+async fn get_address(
+    iface_name: &str,
+    family: IpFamily,
+) -> Result<Option<String>, CliError> {
+    let mut iface_filter = nispor::NetStateIfaceFilter::minimum();
+    iface_filter.iface_name = Some(iface_name.to_string());
+    iface_filter.include_ip_address = true;
+    let mut filter = nispor::NetStateFilter::minimum();
+    filter.iface = Some(iface_filter);
+    let state = nispor::NetState::retrieve_with_filter_async(&filter).await?;
+    let Some(iface) = state.ifaces.get(iface_name) else {
+        return Ok(None);
+    };
+
+    Ok(match family {
+        IpFamily::Ipv4 => iface
+            .ipv4
+            .as_ref()
+            .and_then(|i| i.addresses.first())
+            .map(|a| format!("{}/{}", a.address, a.prefix_len)),
+        IpFamily::Ipv6Global | IpFamily::Ipv6LinkLocal => iface
+            .ipv6
+            .as_ref()
+            .and_then(|i| {
+                i.addresses.iter().find(|a| {
+                    let is_link_local = a
+                        .address
+                        .parse::<Ipv6Addr>()
+                        .map(|a| a.is_unicast_link_local())
+                        .unwrap_or_default();
+                    is_link_local == (family == IpFamily::Ipv6LinkLocal)
+                })
+            })
+            .map(|a| format!("{}/{}", a.address, a.prefix_len)),
+    })
+}
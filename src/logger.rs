@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::net::UnixDatagram;
+use std::sync::Mutex;
+
+use serde::Deserialize;
+
+use crate::CliError;
+
+const ENV_LOG_LEVEL: &str = "MSB_LOG";
+const LOG_FILE_NAME: &str = "msb/msb.log";
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+const DEFAULT_MAX_SIZE: u64 = 1 << 20; // 1 MiB
+const DEFAULT_ROTATE: u32 = 3;
+// Dependencies like nispor are too verbose to be useful in our log
+const DEPENDENCY_MAX_LEVEL: log::LevelFilter = log::LevelFilter::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogTarget {
+    File,
+    Stderr,
+    Journald,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct LogConfig {
+    /// One of `off`, `error`, `warn`, `info`, `debug`, `trace`. Overridden
+    /// by `MSB_LOG` environment variable. Logs of dependencies are limited
+    /// to `error`.
+    pub(crate) level: String,
+    pub(crate) target: LogTarget,
+    /// Log file path, default to `$XDG_STATE_HOME/msb/msb.log`
+    pub(crate) path: Option<String>,
+    /// Rotate log file when its size exceeds this many bytes
+    pub(crate) max_size: u64,
+    /// Number of rotated log files to keep
+    pub(crate) rotate: u32,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            target: LogTarget::File,
+            path: None,
+            max_size: DEFAULT_MAX_SIZE,
+            rotate: DEFAULT_ROTATE,
+        }
+    }
+}
+
+impl LogConfig {
+    pub(crate) fn validate(&self) -> Result<(), CliError> {
+        parse_level(&self.level)?;
+        Ok(())
+    }
+}
+
+pub(crate) fn parse_level(level: &str) -> Result<log::LevelFilter, CliError> {
+    match level.to_lowercase().as_str() {
+        "off" => Ok(log::LevelFilter::Off),
+        "error" => Ok(log::LevelFilter::Error),
+        "warn" => Ok(log::LevelFilter::Warn),
+        "info" => Ok(log::LevelFilter::Info),
+        "debug" => Ok(log::LevelFilter::Debug),
+        "trace" => Ok(log::LevelFilter::Trace),
+        _ => Err(format!(
+            "invalid log level '{level}', should be one of \
+             off, error, warn, info, debug, trace"
+        )
+        .into()),
+    }
+}
+
+/// Setup global logger. Fallback to stderr when failed to open the log
+/// file or journald socket.
+/// The log level is taken from `level_override`, `MSB_LOG` environment
+/// variable and then config.
+pub(crate) fn init_logger(
+    config: &LogConfig,
+    level_override: Option<&str>,
+) -> Result<(), CliError> {
+    let level = match (level_override, std::env::var(ENV_LOG_LEVEL)) {
+        (Some(l), _) => parse_level(l)?,
+        (None, Ok(l)) if !l.is_empty() => parse_level(&l)?,
+        _ => parse_level(&config.level)?,
+    };
+
+    let (writer, error) = match LogWriter::new(config) {
+        Ok(w) => (w, None),
+        Err(e) => (LogWriter::Stderr, Some(e)),
+    };
+
+    log::set_boxed_logger(Box::new(MsbLogger {
+        level,
+        writer: Mutex::new(writer),
+    }))
+    .map_err(|e| format!("Failed to set logger: {e}"))?;
+    log::set_max_level(level);
+
+    if let Some(e) = error {
+        log::warn!("Fallback to log into stderr: {e}");
+    }
+    Ok(())
+}
+
+struct MsbLogger {
+    level: log::LevelFilter,
+    writer: Mutex<LogWriter>,
+}
+
+impl log::Log for MsbLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        let target = metadata.target();
+        let is_msb = target == "msb" || target.starts_with("msb::");
+        let max_level = if is_msb {
+            self.level
+        } else {
+            self.level.min(DEPENDENCY_MAX_LEVEL)
+        };
+        metadata.level() <= max_level
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(mut writer) = self.writer.lock() {
+            // Nowhere to report failure of logging
+            writer.write(record).ok();
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+enum LogWriter {
+    File(LogFile),
+    Stderr,
+    Journald(UnixDatagram),
+}
+
+impl LogWriter {
+    fn new(config: &LogConfig) -> Result<Self, CliError> {
+        match config.target {
+            LogTarget::File => Ok(Self::File(LogFile::open(config)?)),
+            LogTarget::Stderr => Ok(Self::Stderr),
+            LogTarget::Journald => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(JOURNALD_SOCKET).map_err(|e| {
+                    format!("Failed to connect {JOURNALD_SOCKET}: {e}")
+                })?;
+                Ok(Self::Journald(socket))
+            }
+        }
+    }
+
+    fn write(&mut self, record: &log::Record) -> Result<(), CliError> {
+        match self {
+            Self::File(f) => f.write(&format_line(record)),
+            Self::Stderr => {
+                Ok(std::io::stderr()
+                    .write_all(format_line(record).as_bytes())?)
+            }
+            Self::Journald(socket) => {
+                socket.send(&journald_message(record))?;
+                Ok(())
+            }
+        }
+    }
+}
+
+fn format_line(record: &log::Record) -> String {
+    format!(
+        "{} {:<5} {}\n",
+        chrono::offset::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+        record.level(),
+        record.args()
+    )
+}
+
+// Following the native protocol of systemd-journald.service(8).
+// MESSAGE is serialized in binary form as it might contain newline.
+fn journald_message(record: &log::Record) -> Vec<u8> {
+    let priority = match record.level() {
+        log::Level::Error => 3,
+        log::Level::Warn => 4,
+        log::Level::Info => 6,
+        log::Level::Debug | log::Level::Trace => 7,
+    };
+    let msg = record.args().to_string();
+    let mut data =
+        format!("PRIORITY={priority}\nSYSLOG_IDENTIFIER=msb\nMESSAGE\n")
+            .into_bytes();
+    data.extend_from_slice(&(msg.len() as u64).to_le_bytes());
+    data.extend_from_slice(msg.as_bytes());
+    data.push(b'\n');
+    data
+}
+
+struct LogFile {
+    path: String,
+    fd: std::fs::File,
+    size: u64,
+    max_size: u64,
+    rotate: u32,
+}
+
+impl LogFile {
+    fn open(config: &LogConfig) -> Result<Self, CliError> {
+        let path = match config.path.as_ref() {
+            Some(p) => p.to_string(),
+            None => default_log_path().ok_or_else(|| {
+                CliError::from("Neither XDG_STATE_HOME nor HOME is defined")
+            })?,
+        };
+        if let Some(dir) = std::path::Path::new(&path).parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let fd = open_log_file(&path)?;
+        let size = fd.metadata()?.len();
+        Ok(Self {
+            path,
+            fd,
+            size,
+            max_size: config.max_size,
+            rotate: config.rotate,
+        })
+    }
+
+    fn write(&mut self, line: &str) -> Result<(), CliError> {
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        self.fd.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    // msb.log -> msb.log.1 -> msb.log.2 ... and discard the oldest one
+    fn rotate(&mut self) -> Result<(), CliError> {
+        if self.rotate > 0 {
+            for i in (1..self.rotate).rev() {
+                let old = format!("{}.{i}", self.path);
+                if std::path::Path::new(&old).exists() {
+                    std::fs::rename(&old, format!("{}.{}", self.path, i + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, format!("{}.1", self.path))?;
+        } else {
+            std::fs::remove_file(&self.path)?;
+        }
+        self.fd = open_log_file(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn open_log_file(path: &str) -> Result<std::fs::File, CliError> {
+    std::fs::File::options()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| format!("Failed to open log file {path}: {e}").into())
+}
+
+fn default_log_path() -> Option<String> {
+    if let Ok(dir) = std::env::var("XDG_STATE_HOME") {
+        if !dir.is_empty() {
+            return Some(format!("{dir}/{LOG_FILE_NAME}"));
+        }
+    }
+    std::env::var("HOME")
+        .ok()
+        .map(|home| format!("{home}/.local/state/{LOG_FILE_NAME}"))
+}
@@ -2,127 +2,256 @@
 
 mod aqi;
 mod battery;
+mod battery_health;
+mod block;
+mod cli;
+mod config;
 mod cpu;
+mod cpufreq;
+mod disk;
+mod diskio;
 mod error;
+mod ethernet;
 mod fs;
+mod ip;
+mod logger;
+mod memory;
+mod net;
+mod notify;
+mod psi;
 mod rate;
 mod sound;
+mod swaybar;
 mod temp;
+mod time;
 mod wifi;
 
-use std::io::Write;
-
-use serde::{Deserialize, Serialize};
+use clap::Parser;
 
+use crate::block::BlockUpdate;
+use crate::cli::{MsbCli, MsbCommand};
+use crate::config::{BlockConfig, BlockEntry, MsbConfig};
 use crate::error::CliError;
+use crate::swaybar::{
+    SwayBarApiVersion, SwayBarBlock, SwayBarClickEvent, CONT_SIGNAL,
+    STOP_SIGNAL,
+};
 
 pub(crate) const COLOR_RED: &str = "#FF0000";
 pub(crate) const COLOR_YELLOW: &str = "#E9F505";
 // pub(crate) const COLOR_GREEN: &str = "#00FF00";
 
-const IFACE_NAME: &str = "wlan0";
-const INTERVAL: u64 = 10;
-
-// Following manpage swaybar-protocol
+const SIGCONT: std::ffi::c_int = 18;
+const INSTANCE_SEPARATOR: char = ':';
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
-struct SwayBarApiVersion {
-    version: u8,
-    click_events: bool,
+// Cached blocks of each producer in the order of config
+#[derive(Debug, Default)]
+struct BlockCache {
+    producers: Vec<Vec<SwayBarBlock>>,
 }
 
-impl Default for SwayBarApiVersion {
-    fn default() -> Self {
-        SwayBarApiVersion {
-            version: 1,
-            click_events: false,
+impl BlockCache {
+    fn new(count: usize) -> Self {
+        Self {
+            producers: vec![Vec::new(); count],
         }
     }
+
+    /// Return true if changed
+    fn update(&mut self, update: BlockUpdate) -> bool {
+        match self.producers.get_mut(update.index) {
+            Some(blocks) if blocks != &update.blocks => {
+                *blocks = update.blocks;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Index of producer generated the block clicked. The producer index
+    /// prefixed to `instance` by `emit()` is stripped from the event.
+    fn find_producer(&self, event: &mut SwayBarClickEvent) -> Option<usize> {
+        let instance = event.instance.take()?;
+        let (index, instance) = match instance.split_once(INSTANCE_SEPARATOR) {
+            Some((index, instance)) => (index, Some(instance.to_string())),
+            None => (instance.as_str(), None),
+        };
+        let index = index.parse::<usize>().ok()?;
+        event.instance = instance;
+        (index < self.producers.len()).then_some(index)
+    }
+
+    // Repeated block types like two `time` blocks share the same name, the
+    // producer index is prefixed to `instance` to route click events.
+    fn emit(&self) -> Result<(), CliError> {
+        let blocks: Vec<SwayBarBlock> = self
+            .producers
+            .iter()
+            .enumerate()
+            .flat_map(|(index, blocks)| {
+                blocks.iter().map(move |block| {
+                    let mut block = block.clone();
+                    block.instance = Some(match block.instance.take() {
+                        Some(i) => format!("{index}{INSTANCE_SEPARATOR}{i}"),
+                        None => index.to_string(),
+                    });
+                    block
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&blocks)?);
+        print!(",");
+        Ok(())
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
-struct SwayBarBlock {
-    name: String,
-    full_text: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    min_width: Option<usize>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    urgent: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    color: Option<String>,
+fn hook_signal(
+    signal: std::ffi::c_int,
+) -> Result<tokio::signal::unix::Signal, CliError> {
+    tokio::signal::unix::signal(tokio::signal::unix::SignalKind::from_raw(
+        signal,
+    ))
+    .map_err(|e| format!("tokio failed to hook on signal {signal}: {e}").into())
 }
 
-const SIGCONT: std::ffi::c_int = 18;
+#[tokio::main()]
+async fn main() -> Result<(), CliError> {
+    let cli = MsbCli::parse();
+    let command = cli.command.unwrap_or_default();
 
-fn log(line: &str) -> Result<(), CliError> {
-    let mut fd = std::fs::File::options()
-        .create(true)
-        .append(true)
-        .open("/tmp/msb.log")?;
-    Ok(writeln!(fd, "{line}")?)
-}
+    let config = match cli.load_config() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    crate::logger::init_logger(&config.log, cli.log_level.as_deref())?;
 
-fn get_time() -> SwayBarBlock {
-    let now = chrono::offset::Local::now();
-    SwayBarBlock {
-        name: "time".to_string(),
-        full_text: format!("{}", now.format("%Y-%m-%d %H:%M:%S")),
-        ..Default::default()
+    match command {
+        MsbCommand::Run => run(config).await,
+        MsbCommand::Once => once(config).await,
+        MsbCommand::ListBlocks => list_blocks(config.interval).await,
+        MsbCommand::CheckConfig => {
+            match cli.config_path()? {
+                Some(path) => println!(
+                    "Config file {path} is valid, {} blocks defined",
+                    config.blocks.len()
+                ),
+                None => println!(
+                    "No config file found, using default config with {} \
+                     blocks",
+                    config.blocks.len()
+                ),
+            }
+            Ok(())
+        }
     }
 }
 
-async fn emit_status(
-    aqi_fether: &crate::aqi::AqiFetcher,
-) -> Result<(), CliError> {
+async fn once(config: MsbConfig) -> Result<(), CliError> {
     let mut blocks: Vec<SwayBarBlock> = Vec::new();
-
-    if let Some(b) = aqi_fether.get() {
-        blocks.push(b);
-    }
-    blocks.push(crate::rate::get_rate(IFACE_NAME).await?);
-    blocks.push(crate::wifi::get_wifi(IFACE_NAME).await?);
-    blocks.push(crate::cpu::get_cpu().await?);
-    for block in crate::temp::get_temp()? {
-        blocks.push(block);
-    }
-    blocks.push(crate::sound::get_sound()?);
-    if let Some(b) = crate::battery::get_battery()? {
-        blocks.push(b);
+    for update in
+        crate::block::refresh_once(&config.blocks, config.interval).await
+    {
+        if let Some(e) = update.error {
+            eprintln!("{e}");
+        }
+        blocks.extend(update.blocks);
     }
-    blocks.push(get_time());
+    println!("{}", serde_json::to_string(&blocks)?);
+    Ok(())
+}
 
-    println!("{}", serde_json::to_string_pretty(&blocks)?);
-    print!(",");
+async fn list_blocks(interval: u64) -> Result<(), CliError> {
+    let entries: Vec<BlockEntry> =
+        BlockConfig::all().into_iter().map(Into::into).collect();
+    let width = entries
+        .iter()
+        .map(|e| e.block.type_name().len())
+        .max()
+        .unwrap_or_default();
+    for update in crate::block::refresh_once(&entries, interval).await {
+        let type_name = entries[update.index].block.type_name();
+        if let Some(e) = update.error {
+            println!("{type_name:<width$} error: {e}");
+        } else if update.blocks.is_empty() {
+            println!("{type_name:<width$} (not detected)");
+        } else {
+            for block in update.blocks {
+                println!("{type_name:<width$} {}", block.full_text);
+            }
+        }
+    }
     Ok(())
 }
 
-#[tokio::main()]
-async fn main() -> Result<(), CliError> {
-    let mut continue_stream = tokio::signal::unix::signal(
-        tokio::signal::unix::SignalKind::from_raw(SIGCONT),
-    )
-    .map_err(|e| format!("tokio failed to hook on signal SIGCONT: {e}"))?;
+async fn run(config: MsbConfig) -> Result<(), CliError> {
+    // Besides the stop/cont signals we requested in header, still treat
+    // SIGCONT as resume in case we got SIGSTOP from elsewhere.
+    let mut stop_stream = hook_signal(STOP_SIGNAL)?;
+    let mut cont_stream = hook_signal(CONT_SIGNAL)?;
+    let mut sigcont_stream = hook_signal(SIGCONT)?;
 
-    let mut interval =
-        tokio::time::interval(std::time::Duration::from_secs(INTERVAL));
+    // Block producers should stop polling when this is true
+    let (pause_sender, pause_receiver) = tokio::sync::watch::channel(false);
 
     println!("{}", serde_json::to_string(&SwayBarApiVersion::default())?);
 
     println!("[");
 
-    let aqi_fether = crate::aqi::AqiFetcher::new().await?;
+    let (click_sender, mut click_receiver) = tokio::sync::mpsc::channel(16);
+    crate::swaybar::spawn_click_reader(click_sender);
+
+    let (mut update_receiver, block_click_senders) = crate::block::spawn_blocks(
+        &config.blocks,
+        config.interval,
+        pause_receiver,
+        false,
+    );
+
+    let mut cache = BlockCache::new(config.blocks.len());
 
     loop {
         tokio::select! {
-            _ = interval.tick() => {
-                if let Err(e) = emit_status(&aqi_fether).await {
-                    log(&e.to_string())?;
+            Some(update) = update_receiver.recv() => {
+                let mut changed = cache.update(update);
+                // Merge updates arrived together into single status line
+                while let Ok(update) = update_receiver.try_recv() {
+                    changed |= cache.update(update);
+                }
+                if changed {
+                    if let Err(e) = cache.emit() {
+                        log::error!("Failed to emit status: {e}");
+                    }
                 }
             }
-            _ = continue_stream.recv() => {
-                log("Got continue signal")?;
-                if let Err(e) = emit_status(&aqi_fether).await {
-                    log(&e.to_string())?;
+            Some(mut event) = click_receiver.recv() => {
+                log::debug!("Got click event {event:?}");
+                if let Some(sender) = cache
+                    .find_producer(&mut event)
+                    .and_then(|i| block_click_senders.get(i))
+                {
+                    // Never wait on busy or paused producer
+                    if let Err(e) = sender.try_send(event) {
+                        log::warn!("Dropping click event: {e}");
+                    }
+                }
+            }
+            _ = stop_stream.recv() => {
+                log::info!("Got stop signal, pausing");
+                pause_sender.send_replace(true);
+            }
+            _ = async {
+                tokio::select! {
+                    _ = cont_stream.recv() => (),
+                    _ = sigcont_stream.recv() => (),
+                }
+            } => {
+                log::info!("Got continue signal, resuming");
+                pause_sender.send_replace(false);
+                if let Err(e) = cache.emit() {
+                    log::error!("Failed to emit status: {e}");
                 }
             }
         }
@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{
+    block::MsbBlock,
+    fs::{read_dir, read_file},
+    psi::{PsiResource, PsiStat},
+    rate::bytes_to_human,
+    CliError, SwayBarBlock,
+};
+
+pub(crate) const SYSFS_BLOCK_DIR: &str = "/sys/block";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct MemoryConfig {
+    /// Used memory percentage to show in yellow
+    pub(crate) warning: u64,
+    /// Used memory percentage to show in red
+    pub(crate) critical: u64,
+    /// Whether to show used swap when swap is enabled
+    pub(crate) swap: bool,
+    /// Whether to show original and compressed size of zram devices
+    pub(crate) zram: bool,
+    /// Whether to show memory pressure from `/proc/pressure/memory`
+    pub(crate) psi: bool,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            warning: 70,
+            critical: 90,
+            swap: true,
+            zram: true,
+            psi: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct MemoryBlock {
+    config: MemoryConfig,
+}
+
+impl MemoryBlock {
+    pub(crate) fn new(config: MemoryConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl MsbBlock for MemoryBlock {
+    const NAME: &'static str = "memory";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        Ok(vec![get_memory(&self.config)?])
+    }
+}
+
+fn get_memory(config: &MemoryConfig) -> Result<SwayBarBlock, CliError> {
+    let info = MemInfo::retrieve()?;
+    let used = info.total.saturating_sub(info.available);
+    let percent = used
+        .saturating_mul(100)
+        .checked_div(info.total)
+        .unwrap_or(0);
+
+    let short_text = format!(
+        "M: {} ({} free)",
+        bytes_to_human(used),
+        bytes_to_human(info.available)
+    );
+    let mut full_text = short_text.clone();
+
+    if config.swap && info.swap_total > 0 {
+        let swap_used = info.swap_total.saturating_sub(info.swap_free);
+        full_text.push_str(&format!(" S: {}", bytes_to_human(swap_used)));
+    }
+    if config.zram {
+        if let Some(zram) = ZramStat::retrieve()? {
+            full_text.push_str(&format!(
+                " Z: {}→{}",
+                bytes_to_human(zram.orig_data_size),
+                bytes_to_human(zram.mem_used_total)
+            ));
+        }
+    }
+    if config.psi {
+        let psi = PsiStat::retrieve(PsiResource::Memory)?;
+        full_text.push_str(&format!(" P: {:.1}%", psi.some_avg10));
+    }
+
+    let color = if percent >= config.critical {
+        Some(crate::COLOR_RED.to_string())
+    } else if percent >= config.warning {
+        Some(crate::COLOR_YELLOW.to_string())
+    } else {
+        None
+    };
+
+    Ok(SwayBarBlock {
+        name: "memory".into(),
+        short_text: (full_text != short_text).then_some(short_text),
+        full_text,
+        color,
+        ..Default::default()
+    })
+}
+
+/// Sizes in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct MemInfo {
+    total: u64,
+    available: u64,
+    swap_total: u64,
+    swap_free: u64,
+}
+
+impl MemInfo {
+    fn retrieve() -> Result<Self, CliError> {
+        let content = read_file("/proc/meminfo")?;
+        // Line format:
+        //  MemTotal:        6147400 kB
+        let entries: HashMap<&str, u64> = content
+            .lines()
+            .filter_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                let value = value.split_ascii_whitespace().next()?;
+                Some((key, value.parse::<u64>().ok()?))
+            })
+            .collect();
+        let get = |key: &str| -> Result<u64, CliError> {
+            entries.get(key).map(|kib| kib * 1024).ok_or_else(|| {
+                CliError::from(format!("No {key} found in /proc/meminfo"))
+            })
+        };
+        Ok(Self {
+            total: get("MemTotal")?,
+            available: get("MemAvailable")?,
+            // Kernel without CONFIG_SWAP has no swap entries
+            swap_total: get("SwapTotal").unwrap_or_default(),
+            swap_free: get("SwapFree").unwrap_or_default(),
+        })
+    }
+}
+
+/// Summary of all zram devices, sizes in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct ZramStat {
+    /// Uncompressed size of data stored
+    orig_data_size: u64,
+    /// Memory allocated including fragmentation and metadata
+    mem_used_total: u64,
+}
+
+impl ZramStat {
+    /// Return None when no zram device is in use
+    fn retrieve() -> Result<Option<Self>, CliError> {
+        let mut ret: Option<Self> = None;
+        if !std::fs::exists(SYSFS_BLOCK_DIR).unwrap_or_default() {
+            return Ok(ret);
+        }
+        for name in read_dir(SYSFS_BLOCK_DIR)? {
+            if !name.starts_with("zram") {
+                continue;
+            }
+            // Line format of mm_stat:
+            //  orig_data_size compr_data_size mem_used_total mem_limit ...
+            let Ok(content) =
+                read_file(&format!("{SYSFS_BLOCK_DIR}/{name}/mm_stat"))
+            else {
+                continue;
+            };
+            let values: Vec<u64> = content
+                .split_ascii_whitespace()
+                .map(|v| v.parse::<u64>())
+                .collect::<Result<_, _>>()?;
+            if let (Some(orig), Some(used)) = (values.first(), values.get(2)) {
+                if *orig == 0 {
+                    continue;
+                }
+                let stat = ret.get_or_insert_with(Self::default);
+                stat.orig_data_size += orig;
+                stat.mem_used_total += used;
+            }
+        }
+        Ok(ret)
+    }
+}
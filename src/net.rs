@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use rtnetlink::{
+    constants::{
+        RTMGRP_IPV4_IFADDR, RTMGRP_IPV4_ROUTE, RTMGRP_IPV6_IFADDR,
+        RTMGRP_IPV6_ROUTE, RTMGRP_LINK,
+    },
+    sys::{AsyncSocket, SocketAddr},
+};
+use tokio::sync::watch;
+
+use crate::CliError;
+
+/// Interface name in config meaning the one holding default route
+pub(crate) const IFACE_AUTO: &str = "auto";
+
+const RT_TABLE_MAIN: u8 = 254;
+const NET_EVENT_DELAY: u64 = 200; // 0.2 second
+
+static NET_EVENTS: OnceLock<watch::Sender<()>> = OnceLock::new();
+
+/// Resolve interface name in config. Return None when `auto` is
+/// requested but there is no default route.
+pub(crate) async fn resolve_iface(
+    iface: &str,
+) -> Result<Option<String>, CliError> {
+    if iface == IFACE_AUTO {
+        get_default_route_iface().await
+    } else {
+        Ok(Some(iface.to_string()))
+    }
+}
+
+/// Interface of the default route with the lowest metric, IPv4 is
+/// preferred over IPv6.
+pub(crate) async fn get_default_route_iface() -> Result<Option<String>, CliError>
+{
+    let mut route_filter = nispor::NetStateRouteFilter::default();
+    route_filter.table = Some(RT_TABLE_MAIN);
+    let mut filter = nispor::NetStateFilter::minimum();
+    filter.route = Some(route_filter);
+    let state = nispor::NetState::retrieve_with_filter_async(&filter).await?;
+
+    let mut default_routes: Vec<&nispor::Route> = state
+        .routes
+        .iter()
+        .filter(|r| {
+            r.dst.is_none()
+                && r.oif.is_some()
+                && r.route_type == nispor::RouteType::Unicast
+        })
+        .collect();
+    default_routes.sort_by_key(|r| {
+        (
+            r.address_family != nispor::AddressFamily::IPv4,
+            r.metric.unwrap_or_default(),
+        )
+    });
+    Ok(default_routes.first().and_then(|r| r.oif.clone()))
+}
+
+/// Wake up network blocks on link, address and route changes
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NetEventListener {
+    receiver: Option<watch::Receiver<()>>,
+}
+
+impl NetEventListener {
+    pub(crate) async fn wait(&mut self) {
+        let receiver = self.receiver.get_or_insert_with(|| {
+            NET_EVENTS.get_or_init(spawn_net_monitor).subscribe()
+        });
+        // The sender is static, hence never closed
+        if receiver.changed().await.is_ok() {
+            // Events come in bursts, e.g. link up followed by addresses
+            // and routes, wait a while to refresh only once.
+            tokio::time::sleep(Duration::from_millis(NET_EVENT_DELAY)).await;
+            receiver.borrow_and_update();
+        }
+    }
+}
+
+// Network blocks fallback to refresh at interval only when failed to
+// subscribe rtnetlink multicast groups.
+fn spawn_net_monitor() -> watch::Sender<()> {
+    let (sender, _) = watch::channel(());
+    let monitor_sender = sender.clone();
+    tokio::spawn(async move {
+        if let Err(e) = monitor_net_events(&monitor_sender).await {
+            log::warn!("Failed to monitor network changes: {e}");
+        }
+    });
+    sender
+}
+
+async fn monitor_net_events(
+    sender: &watch::Sender<()>,
+) -> Result<(), CliError> {
+    let (mut conn, _, mut messages) = rtnetlink::new_connection()?;
+    let groups = RTMGRP_LINK
+        | RTMGRP_IPV4_IFADDR
+        | RTMGRP_IPV6_IFADDR
+        | RTMGRP_IPV4_ROUTE
+        | RTMGRP_IPV6_ROUTE;
+    conn.socket_mut()
+        .socket_mut()
+        .bind(&SocketAddr::new(0, groups))?;
+    tokio::spawn(conn);
+    log::debug!("Monitoring rtnetlink link, address and route changes");
+
+    while let Some((message, _)) = messages.next().await {
+        log::debug!("Got rtnetlink event {:?}", message.header.message_type);
+        sender.send_replace(());
+    }
+    Err("rtnetlink connection closed".into())
+}
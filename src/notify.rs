@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// Following the Desktop Notifications Specification
+
+use std::collections::HashMap;
+
+use zbus::zvariant::Value;
+
+use crate::CliError;
+
+const DBUS_NAME: &str = "org.freedesktop.Notifications";
+const DBUS_PATH: &str = "/org/freedesktop/Notifications";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum Urgency {
+    Normal = 1,
+    Critical = 2,
+}
+
+/// Send desktop notification, return notification ID.
+pub(crate) async fn send_notification(
+    summary: &str,
+    body: &str,
+    icon: &str,
+    urgency: Urgency,
+) -> Result<u32, CliError> {
+    let conn = zbus::Connection::session().await?;
+    let hints: HashMap<&str, Value> =
+        HashMap::from([("urgency", Value::U8(urgency as u8))]);
+    let actions: Vec<&str> = Vec::new();
+    let reply = conn
+        .call_method(
+            Some(DBUS_NAME),
+            DBUS_PATH,
+            Some(DBUS_NAME),
+            "Notify",
+            // app_name, replaces_id, app_icon, summary, body, actions,
+            // hints, expire_timeout
+            &("msb", 0u32, icon, summary, body, actions, hints, -1i32),
+        )
+        .await?;
+    Ok(reply.body().deserialize::<u32>()?)
+}
+
+impl From<zbus::Error> for CliError {
+    fn from(e: zbus::Error) -> Self {
+        Self {
+            error_msg: format!("zbus::Error: {e}"),
+        }
+    }
+}
+
+impl From<zbus::zvariant::Error> for CliError {
+    fn from(e: zbus::zvariant::Error) -> Self {
+        Self {
+            error_msg: format!("zbus::zvariant::Error: {e}"),
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// Pressure Stall Information, see kernel document
+// Documentation/accounting/psi.rst
+
+use serde::Deserialize;
+
+use crate::{block::MsbBlock, fs::read_file, CliError, SwayBarBlock};
+
+const PROC_PRESSURE_DIR: &str = "/proc/pressure";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PsiResource {
+    Cpu,
+    Memory,
+    Io,
+}
+
+impl PsiResource {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Memory => "memory",
+            Self::Io => "io",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct PsiConfig {
+    /// Resources to show pressure of, `cpu`, `memory` or `io`
+    pub(crate) resources: Vec<PsiResource>,
+    /// Stall percentage of last 10 seconds to show in yellow
+    pub(crate) warning: u64,
+    /// Stall percentage of last 10 seconds to show in red
+    pub(crate) critical: u64,
+}
+
+impl Default for PsiConfig {
+    fn default() -> Self {
+        Self {
+            resources: vec![
+                PsiResource::Cpu,
+                PsiResource::Memory,
+                PsiResource::Io,
+            ],
+            warning: 10,
+            critical: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PsiBlock {
+    config: PsiConfig,
+}
+
+impl PsiBlock {
+    pub(crate) fn new(config: PsiConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl MsbBlock for PsiBlock {
+    const NAME: &'static str = "psi";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        Ok(vec![get_psi(&self.config)?])
+    }
+}
+
+fn get_psi(config: &PsiConfig) -> Result<SwayBarBlock, CliError> {
+    let mut items: Vec<String> = Vec::new();
+    let mut max = 0f64;
+    for resource in &config.resources {
+        let stat = PsiStat::retrieve(*resource)?;
+        items.push(format!("{} {:.1}%", resource.name(), stat.some_avg10));
+        max = max.max(stat.some_avg10);
+    }
+    let color = if max >= config.critical as f64 {
+        Some(crate::COLOR_RED.to_string())
+    } else if max >= config.warning as f64 {
+        Some(crate::COLOR_YELLOW.to_string())
+    } else {
+        None
+    };
+
+    Ok(SwayBarBlock {
+        name: "psi".into(),
+        full_text: format!("P: {}", items.join(" ")),
+        short_text: Some(format!("P: {max:.1}%")),
+        color,
+        ..Default::default()
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct PsiStat {
+    /// Percentage of time in last 10 seconds that at least one task was
+    /// stalled on the resource
+    pub(crate) some_avg10: f64,
+}
+
+impl PsiStat {
+    pub(crate) fn retrieve(resource: PsiResource) -> Result<Self, CliError> {
+        let file_path = format!("{PROC_PRESSURE_DIR}/{}", resource.name());
+        let content = read_file(&file_path).map_err(|e| {
+            CliError::from(format!(
+                "Failed to read {file_path}, is CONFIG_PSI enabled? {e}"
+            ))
+        })?;
+        let mut ret = Self::default();
+        // Line format:
+        //  some avg10=0.00 avg60=0.00 avg300=0.00 total=0
+        //  full avg10=0.00 avg60=0.00 avg300=0.00 total=0
+        for line in content.lines() {
+            let mut items = line.split_ascii_whitespace();
+            if items.next() != Some("some") {
+                continue;
+            }
+            ret.some_avg10 = items
+                .find_map(|item| item.strip_prefix("avg10="))
+                .map(|v| v.parse::<f64>())
+                .transpose()?
+                .unwrap_or_default();
+        }
+        Ok(ret)
+    }
+}
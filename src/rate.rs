@@ -1,15 +1,38 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{fs::read_file_as_u64, CliError, SwayBarBlock};
+use std::time::{Duration, Instant};
 
-const INTERVAL: u64 = 500;
+use serde::Deserialize;
+
+use crate::{
+    block::MsbBlock,
+    fs::read_file_as_u64,
+    net::{resolve_iface, NetEventListener, IFACE_AUTO},
+    CliError, SwayBarBlock,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct RateConfig {
+    /// Interface to show traffic rate of, `auto` means the one holding
+    /// default route
+    pub(crate) iface: String,
+}
+
+impl Default for RateConfig {
+    fn default() -> Self {
+        Self {
+            iface: IFACE_AUTO.to_string(),
+        }
+    }
+}
 
 const KIB: u64 = 1 << 10;
 const MIB: u64 = 1 << 20;
 const GIB: u64 = 1 << 30;
 const TIB: u64 = 1 << 40;
 
-fn bytes_to_human(bytes: u64) -> String {
+pub(crate) fn bytes_to_human(bytes: u64) -> String {
     if bytes > TIB {
         format!("{}.{} TiB", bytes / TIB, (bytes % TIB) * 10 / TIB)
     } else if bytes >= GIB {
@@ -23,36 +46,118 @@ fn bytes_to_human(bytes: u64) -> String {
     }
 }
 
-pub(crate) async fn get_rate(
-    iface_name: &str,
-) -> Result<SwayBarBlock, CliError> {
-    let (rx_speed, tx_speed) = get_net_speed(iface_name).await?;
-    Ok(SwayBarBlock {
+/// Bytes per second between two samples of a byte counter. Counter reset
+/// is treated as zero.
+pub(crate) fn bytes_per_second(old: u64, new: u64, elapsed: Duration) -> u64 {
+    new.saturating_sub(old)
+        .saturating_mul(1000)
+        .checked_div(elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone)]
+struct NetBytesSample {
+    iface: String,
+    time: Instant,
+    rx: u64,
+    tx: u64,
+}
+
+impl NetBytesSample {
+    /// Return None when interface does not exist
+    fn retrieve(iface_name: &str) -> Result<Option<Self>, CliError> {
+        Ok(get_net_bytes(iface_name)?.map(|(rx, tx)| Self {
+            iface: iface_name.to_string(),
+            time: Instant::now(),
+            rx,
+            tx,
+        }))
+    }
+
+    /// Bytes per second of RX and TX since old sample. Counter reset by
+    /// interface recreation is treated as no traffic.
+    fn speed_since(&self, old: &Self) -> (u64, u64) {
+        let elapsed = self.time.duration_since(old.time);
+        (
+            bytes_per_second(old.rx, self.rx, elapsed),
+            bytes_per_second(old.tx, self.tx, elapsed),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RateBlock {
+    config: RateConfig,
+    /// Sample of previous refresh
+    last_sample: Option<NetBytesSample>,
+    net_events: NetEventListener,
+}
+
+impl RateBlock {
+    pub(crate) fn new(config: RateConfig) -> Self {
+        Self {
+            config,
+            last_sample: None,
+            net_events: NetEventListener::default(),
+        }
+    }
+}
+
+impl MsbBlock for RateBlock {
+    const NAME: &'static str = "rate";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        let iface = resolve_iface(&self.config.iface).await?;
+        let sample = match iface.as_deref() {
+            Some(iface) => NetBytesSample::retrieve(iface)?,
+            None => None,
+        };
+        // Show 0 B/s when there is nothing to compare with, e.g. first
+        // refresh or default route moved to another interface
+        let (rx_speed, tx_speed) = match (&sample, &self.last_sample) {
+            (Some(new), Some(old)) if new.iface == old.iface => {
+                new.speed_since(old)
+            }
+            _ => (0, 0),
+        };
+        self.last_sample = sample;
+        Ok(vec![get_rate(iface.as_deref(), rx_speed, tx_speed)])
+    }
+
+    // Follow the default route immediately
+    async fn wait_event(&mut self) {
+        self.net_events.wait().await
+    }
+}
+
+// Use `-` as interface name when there is no default route
+fn get_rate(iface: Option<&str>, rx_speed: u64, tx_speed: u64) -> SwayBarBlock {
+    let iface_name = iface.unwrap_or("-");
+    let rx_speed = bytes_to_human(rx_speed);
+    let tx_speed = bytes_to_human(tx_speed);
+    SwayBarBlock {
         name: "rate".into(),
+        instance: iface.map(|i| i.to_string()),
         full_text: format!(
             "{iface_name:>8}: v {: >9}/s ^ {: >9}/s",
             rx_speed, tx_speed
         ),
-        min_width: Some(28),
+        short_text: Some(format!("v {rx_speed}/s ^ {tx_speed}/s")),
+        min_width: Some(28.into()),
         ..Default::default()
-    })
-}
-
-async fn get_net_speed(iface_name: &str) -> Result<(String, String), CliError> {
-    let (cur_rx, cur_tx) = get_net_bytes(iface_name)?;
-    tokio::time::sleep(std::time::Duration::from_millis(INTERVAL)).await;
-    let (new_rx, new_tx) = get_net_bytes(iface_name)?;
-    let rx_speed = (new_rx - cur_rx) * 1000 / INTERVAL;
-    let tx_speed = (new_tx - cur_tx) * 1000 / INTERVAL;
-    Ok((bytes_to_human(rx_speed), bytes_to_human(tx_speed)))
+    }
 }
 
-fn get_net_bytes(iface_name: &str) -> Result<(u64, u64), CliError> {
+fn get_net_bytes(iface_name: &str) -> Result<Option<(u64, u64)>, CliError> {
     let rx_file = format!("/sys/class/net/{}/statistics/rx_bytes", iface_name);
     let tx_file = format!("/sys/class/net/{}/statistics/tx_bytes", iface_name);
     if std::path::Path::new(&rx_file).exists() {
-        Ok((read_file_as_u64(&rx_file)?, read_file_as_u64(&tx_file)?))
+        Ok(Some((
+            read_file_as_u64(&rx_file)?,
+            read_file_as_u64(&tx_file)?,
+        )))
     } else {
-        Err(format!("{rx_file} does not exist").into())
+        log::debug!("Interface {iface_name} does not exist");
+        Ok(None)
     }
 }
@@ -1,16 +1,106 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use std::process::Command;
 use std::str::FromStr;
 
-use crate::{CliError, SwayBarBlock};
+use serde::Deserialize;
+use tokio::process::Command;
 
-pub(crate) fn get_sound() -> Result<SwayBarBlock, CliError> {
+use crate::{
+    block::MsbBlock,
+    swaybar::{BUTTON_LEFT, BUTTON_SCROLL_DOWN, BUTTON_SCROLL_UP},
+    CliError, SwayBarBlock, SwayBarClickEvent,
+};
+
+const DEFAULT_SINK: &str = "@DEFAULT_AUDIO_SINK@";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct SoundConfig {
+    /// wpctl sink ID or name
+    pub(crate) sink: String,
+    /// Volume percentage to change on each scroll
+    pub(crate) step: u8,
+}
+
+impl Default for SoundConfig {
+    fn default() -> Self {
+        Self {
+            sink: DEFAULT_SINK.to_string(),
+            step: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SoundBlock {
+    config: SoundConfig,
+}
+
+impl SoundBlock {
+    pub(crate) fn new(config: SoundConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl MsbBlock for SoundBlock {
+    const NAME: &'static str = "sound";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        Ok(vec![get_sound(&self.config).await?])
+    }
+
+    async fn on_click(
+        &mut self,
+        event: &SwayBarClickEvent,
+    ) -> Result<(), CliError> {
+        handle_click(&self.config, event).await
+    }
+}
+
+// Left click to toggle mute, scroll to change volume
+async fn handle_click(
+    config: &SoundConfig,
+    event: &SwayBarClickEvent,
+) -> Result<(), CliError> {
+    let step = config.step;
+    let args: Vec<String> = match event.button {
+        BUTTON_LEFT => {
+            vec!["set-mute".into(), config.sink.clone(), "toggle".into()]
+        }
+        BUTTON_SCROLL_UP => vec![
+            "set-volume".into(),
+            "--limit".into(),
+            "1.0".into(),
+            config.sink.clone(),
+            format!("{step}%+"),
+        ],
+        BUTTON_SCROLL_DOWN => {
+            vec![
+                "set-volume".into(),
+                config.sink.clone(),
+                format!("{step}%-"),
+            ]
+        }
+        _ => return Ok(()),
+    };
+    let status = Command::new("wpctl").args(&args).status().await?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(
+            format!("Command `wpctl {}` failed: {status}", args.join(" "))
+                .into(),
+        )
+    }
+}
+
+async fn get_sound(config: &SoundConfig) -> Result<SwayBarBlock, CliError> {
     let output: String = String::from_utf8(
         Command::new("wpctl")
             .arg("get-volume")
-            .arg("@DEFAULT_AUDIO_SINK@")
-            .output()?
+            .arg(&config.sink)
+            .output()
+            .await?
             .stdout,
     )?;
     let output = output.trim();
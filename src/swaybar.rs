@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// Following manpage swaybar-protocol
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncBufReadExt;
+use tokio::signal::unix::SignalKind;
+
+use crate::CliError;
+
+pub(crate) const BUTTON_LEFT: u32 = 1;
+pub(crate) const BUTTON_RIGHT: u32 = 3;
+pub(crate) const BUTTON_SCROLL_UP: u32 = 4;
+pub(crate) const BUTTON_SCROLL_DOWN: u32 = 5;
+
+// Instead of SIGSTOP which cannot be caught, ask swaybar to send SIGUSR1
+// when bar is hidden so we can pause all the polling. Signal numbers
+// differ among architectures.
+pub(crate) const STOP_SIGNAL: std::ffi::c_int =
+    SignalKind::user_defined1().as_raw_value();
+pub(crate) const CONT_SIGNAL: std::ffi::c_int =
+    SignalKind::user_defined2().as_raw_value();
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub(crate) struct SwayBarApiVersion {
+    version: u8,
+    click_events: bool,
+    stop_signal: std::ffi::c_int,
+    cont_signal: std::ffi::c_int,
+}
+
+impl Default for SwayBarApiVersion {
+    fn default() -> Self {
+        SwayBarApiVersion {
+            version: 1,
+            click_events: true,
+            stop_signal: STOP_SIGNAL,
+            cont_signal: CONT_SIGNAL,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub(crate) struct SwayBarBlock {
+    pub(crate) name: String,
+    pub(crate) full_text: String,
+    /// Used by swaybar when the bar is too long for the output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) short_text: Option<String>,
+    /// Distinguish blocks sharing the same name, e.g. interface name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) instance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) background: Option<String>,
+    /// Border color
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) border: Option<String>,
+    /// Border height in pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) border_top: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) border_bottom: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) border_left: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) border_right: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) min_width: Option<SwayBarMinWidth>,
+    /// Alignment of text when `min_width` is bigger than text width
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) align: Option<SwayBarAlign>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) urgent: Option<bool>,
+    /// Whether to draw separator after this block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) separator: Option<bool>,
+    /// Gap in pixels after this block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) separator_block_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) markup: Option<SwayBarMarkup>,
+}
+
+/// Minimum width of block, either in pixels or as width of specified text
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(untagged)]
+pub(crate) enum SwayBarMinWidth {
+    Pixels(u32),
+    Text(String),
+}
+
+impl From<u32> for SwayBarMinWidth {
+    fn from(v: u32) -> Self {
+        Self::Pixels(v)
+    }
+}
+
+impl From<&str> for SwayBarMinWidth {
+    fn from(v: &str) -> Self {
+        Self::Text(v.to_string())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SwayBarAlign {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SwayBarMarkup {
+    Pango,
+    None,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(default)]
+pub(crate) struct SwayBarClickEvent {
+    pub(crate) name: String,
+    pub(crate) instance: Option<String>,
+    /// X11 button ID
+    pub(crate) button: u32,
+    /// Event code of the button
+    pub(crate) event: u32,
+    pub(crate) modifiers: Vec<String>,
+    /// Absolute X position of the click
+    pub(crate) x: i32,
+    /// Absolute Y position of the click
+    pub(crate) y: i32,
+    /// X position of the click relative to the top left of the block
+    pub(crate) relative_x: i32,
+    /// Y position of the click relative to the top left of the block
+    pub(crate) relative_y: i32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) scale: f64,
+}
+
+impl SwayBarClickEvent {
+    // swaybar writes an infinite JSON array to stdin with each click event
+    // in its own line:
+    //      [
+    //      {"name": "time", ...}
+    //      ,{"name": "sound", ...}
+    // Return None for the lines holding no event.
+    fn parse_line(line: &str) -> Result<Option<Self>, CliError> {
+        let line = line.trim();
+        let line = line.strip_prefix('[').unwrap_or(line).trim_start();
+        let line = line.strip_prefix(',').unwrap_or(line).trim();
+        if line.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(serde_json::from_str(line)?))
+        }
+    }
+}
+
+/// Read click events from stdin and send them to the channel until stdin
+/// closed or receiver dropped.
+pub(crate) fn spawn_click_reader(
+    sender: tokio::sync::mpsc::Sender<SwayBarClickEvent>,
+) {
+    tokio::spawn(async move {
+        let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+        loop {
+            let line = match lines.next_line().await {
+                Ok(Some(l)) => l,
+                Ok(None) => break,
+                Err(e) => {
+                    log::error!("Failed to read stdin: {e}");
+                    break;
+                }
+            };
+            match SwayBarClickEvent::parse_line(&line) {
+                Ok(Some(event)) => {
+                    if sender.send(event).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => (),
+                Err(e) => {
+                    log::warn!("Invalid click event {line}: {e}");
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVENT: &str = r#"{"name": "time", "instance": "local", "button": 1}"#;
+
+    #[test]
+    fn test_parse_click_line_without_event() {
+        assert_eq!(SwayBarClickEvent::parse_line("").unwrap(), None);
+        assert_eq!(SwayBarClickEvent::parse_line("[").unwrap(), None);
+        assert_eq!(SwayBarClickEvent::parse_line(" [ \n").unwrap(), None);
+        assert_eq!(SwayBarClickEvent::parse_line(",").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_click_line() {
+        let expected = SwayBarClickEvent {
+            name: "time".into(),
+            instance: Some("local".into()),
+            button: BUTTON_LEFT,
+            ..Default::default()
+        };
+        for line in [
+            EVENT.to_string(),
+            format!("[{EVENT}"),
+            format!(",{EVENT}"),
+            format!("  , {EVENT}  "),
+        ] {
+            assert_eq!(
+                SwayBarClickEvent::parse_line(&line).unwrap(),
+                Some(expected.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_click_line_invalid() {
+        assert!(SwayBarClickEvent::parse_line(",{").is_err());
+        assert!(SwayBarClickEvent::parse_line(",,{}").is_err());
+    }
+}
@@ -2,7 +2,10 @@
 
 use std::collections::HashMap;
 
+use serde::Deserialize;
+
 use crate::{
+    block::MsbBlock,
     fs::{read_dir, read_file, read_file_as_u64},
     CliError, SwayBarBlock,
 };
@@ -11,7 +14,47 @@ const HWMON_NAMES: [&str; 3] = ["k10temp", "thinkpad", "coretemp"];
 
 const SYSFS_HWMON_PATH: &str = "/sys/class/hwmon/";
 
-pub(crate) fn get_temp() -> Result<Vec<SwayBarBlock>, CliError> {
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct TempConfig {
+    /// hwmon names to search for CPU temperature, first match wins
+    pub(crate) hwmon_names: Vec<String>,
+    /// Whether to show NVME disk temperature
+    pub(crate) nvme: bool,
+    /// Temperature in Celsius to show in red
+    pub(crate) critical: u64,
+}
+
+impl Default for TempConfig {
+    fn default() -> Self {
+        Self {
+            hwmon_names: HWMON_NAMES.iter().map(|s| s.to_string()).collect(),
+            nvme: true,
+            critical: 80,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct TempBlock {
+    config: TempConfig,
+}
+
+impl TempBlock {
+    pub(crate) fn new(config: TempConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl MsbBlock for TempBlock {
+    const NAME: &'static str = "temp";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        get_temp(&self.config)
+    }
+}
+
+fn get_temp(config: &TempConfig) -> Result<Vec<SwayBarBlock>, CliError> {
     let mut ret: Vec<SwayBarBlock> = Vec::new();
     let mut degree = 0u64;
 
@@ -24,9 +67,8 @@ pub(crate) fn get_temp() -> Result<Vec<SwayBarBlock>, CliError> {
         name_to_path.insert(hwmon_name, subdir);
     }
 
-    for prefered in &HWMON_NAMES {
-        if let Some(hwmon_dir) = name_to_path.get(prefered.to_string().as_str())
-        {
+    for prefered in &config.hwmon_names {
+        if let Some(hwmon_dir) = name_to_path.get(prefered.as_str()) {
             let temp_file_path = format!("{hwmon_dir}/temp1_input");
             if std::path::Path::new(&temp_file_path).is_file() {
                 degree = read_file_as_u64(&temp_file_path)? / 1000;
@@ -34,7 +76,7 @@ pub(crate) fn get_temp() -> Result<Vec<SwayBarBlock>, CliError> {
             }
         }
     }
-    let color = if degree >= 80 {
+    let color = if degree >= config.critical {
         Some(crate::COLOR_RED.to_string())
     } else {
         None
@@ -43,11 +85,15 @@ pub(crate) fn get_temp() -> Result<Vec<SwayBarBlock>, CliError> {
     ret.push(SwayBarBlock {
         name: "temp".into(),
         full_text: format!("🌡: C {degree:>2}°C"),
-        min_width: Some(10),
+        min_width: Some(10.into()),
         color,
         ..Default::default()
     });
 
+    if !config.nvme {
+        return Ok(ret);
+    }
+
     // Get NVME disk temperature
     for subdir in read_dir(SYSFS_HWMON_PATH)? {
         let subdir = format!("{SYSFS_HWMON_PATH}/{subdir}");
@@ -60,7 +106,7 @@ pub(crate) fn get_temp() -> Result<Vec<SwayBarBlock>, CliError> {
         let temp_file_path = format!("{subdir}/temp1_input");
         if std::path::Path::new(&temp_file_path).is_file() {
             degree = read_file_as_u64(&temp_file_path)? / 1000;
-            let color = if degree >= 80 {
+            let color = if degree >= config.critical {
                 Some(crate::COLOR_RED.to_string())
             } else {
                 None
@@ -69,7 +115,7 @@ pub(crate) fn get_temp() -> Result<Vec<SwayBarBlock>, CliError> {
             ret.push(SwayBarBlock {
                 name: "nvme".into(),
                 full_text: format!("🌡: 💾 {degree:>2}°C"),
-                min_width: Some(11),
+                min_width: Some(11.into()),
                 color,
                 ..Default::default()
             });
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::Deserialize;
+
+use crate::{
+    block::MsbBlock, swaybar::BUTTON_LEFT, CliError, SwayBarBlock,
+    SwayBarClickEvent,
+};
+
+const DEFAULT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const DEFAULT_ALT_FORMAT: &str = "%A, %B %d, %Y";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct TimeConfig {
+    /// chrono strftime format
+    pub(crate) format: String,
+    /// chrono strftime format to toggle to on left click
+    pub(crate) alt_format: String,
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        Self {
+            format: DEFAULT_FORMAT.to_string(),
+            alt_format: DEFAULT_ALT_FORMAT.to_string(),
+        }
+    }
+}
+
+impl TimeConfig {
+    pub(crate) fn validate(&self) -> Result<(), CliError> {
+        for format in [&self.format, &self.alt_format] {
+            if chrono::format::StrftimeItems::new(format)
+                .any(|i| i == chrono::format::Item::Error)
+            {
+                return Err(format!("invalid time format '{format}'").into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct TimeBlock {
+    config: TimeConfig,
+    show_alt_format: bool,
+}
+
+impl TimeBlock {
+    pub(crate) fn new(config: TimeConfig) -> Self {
+        Self {
+            config,
+            show_alt_format: false,
+        }
+    }
+}
+
+impl MsbBlock for TimeBlock {
+    const NAME: &'static str = "time";
+    const DEFAULT_INTERVAL: Option<u64> = Some(1);
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        let now = chrono::offset::Local::now();
+        let format = if self.show_alt_format {
+            &self.config.alt_format
+        } else {
+            &self.config.format
+        };
+        Ok(vec![SwayBarBlock {
+            name: "time".to_string(),
+            full_text: format!("{}", now.format(format)),
+            ..Default::default()
+        }])
+    }
+
+    async fn on_click(
+        &mut self,
+        event: &SwayBarClickEvent,
+    ) -> Result<(), CliError> {
+        if event.button == BUTTON_LEFT {
+            self.show_alt_format = !self.show_alt_format;
+        }
+        Ok(())
+    }
+}
@@ -1,54 +1,235 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{CliError, SwayBarBlock};
+use futures_util::TryStreamExt;
+use serde::Deserialize;
+
+use crate::{
+    block::MsbBlock,
+    net::{get_default_route_iface, NetEventListener, IFACE_AUTO},
+    CliError, SwayBarBlock,
+};
 
 // Align with Microsoft `WLAN_ASSOCIATION_ATTRIBUTES`
 const NOISE_FLOOR_DBM: i8 = -100;
 const SIGNAL_MAX_DBM: i8 = -50;
 
-// The clap is not stable feature yet.
-#[allow(clippy::manual_clamp)]
-fn dbm_to_percentage(dbm: i8) -> u8 {
-    let dbm = if dbm > SIGNAL_MAX_DBM {
-        SIGNAL_MAX_DBM
-    } else if dbm < NOISE_FLOOR_DBM {
-        NOISE_FLOOR_DBM
-    } else {
-        dbm
-    };
-    (100.0f64 * (NOISE_FLOOR_DBM - dbm) as f64
-        / (NOISE_FLOOR_DBM - SIGNAL_MAX_DBM) as f64) as u8
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct WifiConfig {
+    /// WiFi interface to show signal strength of, `auto` means the one
+    /// holding default route or the first WiFi interface found
+    pub(crate) iface: String,
+    /// Signal percentage at or below which to show in yellow
+    pub(crate) warning: u8,
+    /// Signal percentage at or below which to show in red
+    pub(crate) critical: u8,
+    /// Signal in dBm treated as 0%, quality is linear between this and
+    /// `signal_max_dbm`
+    pub(crate) noise_floor_dbm: i8,
+    /// Signal in dBm treated as 100%
+    pub(crate) signal_max_dbm: i8,
+    /// Whether to show TX and RX bitrate
+    pub(crate) bitrate: bool,
+    /// Whether to show MAC address of access point
+    pub(crate) bssid: bool,
 }
 
-pub(crate) async fn get_wifi(
-    iface_name: &str,
-) -> Result<SwayBarBlock, CliError> {
+impl Default for WifiConfig {
+    fn default() -> Self {
+        Self {
+            iface: IFACE_AUTO.to_string(),
+            warning: 50,
+            critical: 25,
+            noise_floor_dbm: NOISE_FLOOR_DBM,
+            signal_max_dbm: SIGNAL_MAX_DBM,
+            bitrate: true,
+            bssid: false,
+        }
+    }
+}
+
+impl WifiConfig {
+    pub(crate) fn validate(&self) -> Result<(), CliError> {
+        if self.signal_max_dbm <= self.noise_floor_dbm {
+            return Err(format!(
+                "wifi signal_max_dbm {} should be bigger than \
+                 noise_floor_dbm {}",
+                self.signal_max_dbm, self.noise_floor_dbm
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    fn dbm_to_percentage(&self, dbm: i8) -> u8 {
+        let floor = self.noise_floor_dbm as i32;
+        let max = self.signal_max_dbm as i32;
+        ((dbm as i32 - floor) * 100 / (max - floor)).clamp(0, 100) as u8
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct WifiBlock {
+    config: WifiConfig,
+    net_events: NetEventListener,
+}
+
+impl WifiBlock {
+    pub(crate) fn new(config: WifiConfig) -> Self {
+        Self {
+            config,
+            net_events: NetEventListener::default(),
+        }
+    }
+}
+
+impl MsbBlock for WifiBlock {
+    const NAME: &'static str = "wifi";
+
+    async fn refresh(&mut self) -> Result<Vec<SwayBarBlock>, CliError> {
+        Ok(get_wifi(&self.config).await?.into_iter().collect())
+    }
+
+    // Signal strength is still polled at interval
+    async fn wait_event(&mut self) {
+        self.net_events.wait().await
+    }
+}
+
+/// Return None when `auto` found no WiFi interface
+async fn get_wifi(
+    config: &WifiConfig,
+) -> Result<Option<SwayBarBlock>, CliError> {
     let mut filter = nispor::NetStateFilter::minimum();
     let iface_filter = nispor::NetStateIfaceFilter::minimum();
     filter.iface = Some(iface_filter);
     let state = nispor::NetState::retrieve_with_filter_async(&filter).await?;
-    if let Some(iface) = state.ifaces.get(iface_name) {
-        if let Some(signal) = iface.wifi.as_ref().and_then(|w| w.signal) {
-            let signal = dbm_to_percentage(signal);
-            let color = if signal > 50 {
-                None
-            } else if signal > 25 {
-                Some(crate::COLOR_YELLOW.to_string())
-            } else {
-                Some(crate::COLOR_RED.to_string())
-            };
-            return Ok(SwayBarBlock {
-                name: "wifi".into(),
-                full_text: format!("📶 {signal}%"),
-                color,
-                ..Default::default()
-            });
+    let iface_name = if config.iface == IFACE_AUTO {
+        match get_default_route_iface().await? {
+            Some(i)
+                if state.ifaces.get(&i).is_some_and(|i| i.wifi.is_some()) =>
+            {
+                i
+            }
+            _ => {
+                let mut wifi_ifaces: Vec<&String> = state
+                    .ifaces
+                    .iter()
+                    .filter_map(|(name, i)| i.wifi.as_ref().map(|_| name))
+                    .collect();
+                wifi_ifaces.sort_unstable();
+                match wifi_ifaces.first() {
+                    Some(i) => i.to_string(),
+                    None => return Ok(None),
+                }
+            }
+        }
+    } else {
+        config.iface.clone()
+    };
+    if let Some(iface) = state.ifaces.get(&iface_name) {
+        if let Some(wifi) = iface.wifi.as_ref() {
+            if let Some(dbm) = wifi.signal {
+                // BSSID is optional, do not fail the whole block
+                let bssid = if config.bssid {
+                    get_bssid(iface.index).await.unwrap_or_else(|e| {
+                        log::warn!("Failed to get BSSID of {iface_name}: {e}");
+                        None
+                    })
+                } else {
+                    None
+                };
+                return Ok(Some(wifi_to_block(
+                    config,
+                    &iface.name,
+                    wifi,
+                    dbm,
+                    bssid.as_deref(),
+                )));
+            }
         }
     }
-    Ok(SwayBarBlock {
+    Ok(Some(SwayBarBlock {
         name: "wifi".into(),
+        instance: Some(iface_name),
         full_text: "📶 0%".to_string(),
         color: Some(crate::COLOR_RED.to_string()),
         ..Default::default()
-    })
+    }))
+}
+
+fn wifi_to_block(
+    config: &WifiConfig,
+    iface_name: &str,
+    wifi: &nispor::WifiInfo,
+    dbm: i8,
+    bssid: Option<&str>,
+) -> SwayBarBlock {
+    let signal = config.dbm_to_percentage(dbm);
+    let color = if signal > config.warning {
+        None
+    } else if signal > config.critical {
+        Some(crate::COLOR_YELLOW.to_string())
+    } else {
+        Some(crate::COLOR_RED.to_string())
+    };
+
+    let mut items: Vec<String> = vec!["📶".to_string()];
+    if let Some(ssid) = wifi.ssid.as_ref() {
+        items.push(ssid.to_string());
+    }
+    if let Some(band) = wifi.frequency.and_then(frequency_to_band) {
+        items.push(band.to_string());
+    }
+    items.push(format!("{signal}% {dbm}dBm"));
+    if config.bitrate {
+        // nispor reports bitrate in 100kb/s
+        if let (Some(tx), Some(rx)) = (wifi.tx_bitrate, wifi.rx_bitrate) {
+            items.push(format!("^{}/v{} Mb/s", tx / 10, rx / 10));
+        }
+    }
+    if let Some(bssid) = bssid {
+        items.push(bssid.to_string());
+    }
+
+    SwayBarBlock {
+        name: "wifi".into(),
+        instance: Some(iface_name.to_string()),
+        full_text: items.join(" "),
+        short_text: Some(format!("📶 {signal}%")),
+        color,
+        ..Default::default()
+    }
+}
+
+fn frequency_to_band(mhz: u32) -> Option<&'static str> {
+    match mhz {
+        2400..=2500 => Some("2.4GHz"),
+        4900..=5900 => Some("5GHz"),
+        5925..=7125 => Some("6GHz"),
+        57000..=71000 => Some("60GHz"),
+        _ => None,
+    }
+}
+
+// For interface in station mode, the only station is the access point
+async fn get_bssid(iface_index: u32) -> Result<Option<String>, CliError> {
+    let (conn, handle, _) = wl_nl80211::new_connection()?;
+    tokio::spawn(conn);
+    let mut stations = handle.station().dump(iface_index).execute().await;
+    while let Some(msg) = stations.try_next().await.map_err(|e| {
+        CliError::from(format!("Failed to dump nl80211 station: {e}"))
+    })? {
+        for attr in &msg.payload.attributes {
+            if let wl_nl80211::Nl80211Attr::Mac(mac) = attr {
+                return Ok(Some(
+                    mac.iter()
+                        .map(|b| format!("{b:02x}"))
+                        .collect::<Vec<String>>()
+                        .join(":"),
+                ));
+            }
+        }
+    }
+    Ok(None)
 }